@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::validation::{issue, Validate, ValidationIssue};
+use crate::value::Value;
+
+// Policies between the interpreted stock and the value written to each channel.
+// Channels without a policy (and not affected by a reserve) are written the stock verbatim.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AllocationConfig {
+    #[serde(default)]
+    pub(crate) reserve: Option<Reserve>,
+    #[serde(default)]
+    pub(crate) channels: HashMap<String, ChannelAllocation>, // By platform name.
+}
+
+// Units held for one platform (e.g. the POS)- every other channel is written that much less.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Reserve {
+    pub(crate) platform: String,
+    pub(crate) units: Value,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ChannelAllocation {
+    #[serde(default)]
+    pub(crate) safety_buffer: Value, // Units never shown on this channel.
+    #[serde(default)]
+    pub(crate) share: Option<f64>, // Fraction of the (buffered) stock shown, rounded down.
+    #[serde(default)]
+    pub(crate) hide_below: Option<Value>, // Show none when fewer than this would be shown.
+}
+
+impl ChannelAllocation {
+    // Value to write to the channel for the given stock- never negative.
+    pub fn allocate(&self, stock: Value) -> Value {
+        let mut shown = stock - self.safety_buffer;
+        if let Some(share) = self.share {
+            shown = (shown as f64 * share).floor() as Value;
+        }
+        if self.hide_below.is_some_and(|threshold| shown < threshold) {
+            shown = 0;
+        }
+        shown.max(0)
+    }
+
+    // Least stock that shows this value- for none shown, that is taken to be the buffer alone.
+    pub fn stock(&self, shown: Value) -> Value {
+        let mut stock = shown;
+        if let Some(share) = self.share {
+            stock = (shown as f64 / share).ceil() as Value;
+        }
+        stock + self.safety_buffer
+    }
+}
+
+// Value to write to a channel for the given stock- verbatim without a policy.
+pub fn allocated(allocation: Option<&ChannelAllocation>, stock: Value) -> Value {
+    allocation.map_or(stock, |allocation| allocation.allocate(stock))
+}
+
+// Stock a channel's value was written for- verbatim without a policy.
+pub fn unallocated(allocation: Option<&ChannelAllocation>, shown: Value) -> Value {
+    allocation.map_or(shown, |allocation| allocation.stock(shown))
+}
+
+impl AllocationConfig {
+    // Policy of a channel, with any reserve for another platform folded into its buffer.
+    // None where the stock is written verbatim.
+    pub fn policy(&self, platform: &str) -> Option<ChannelAllocation> {
+        let reserved = self.reserve.as_ref()
+            .filter(|reserve| reserve.platform != platform)
+            .map(|reserve| reserve.units);
+        match (self.channels.get(platform).cloned(), reserved) {
+            (None, None) => None,
+            (policy, reserved) => {
+                let mut policy = policy.unwrap_or_default();
+                policy.safety_buffer += reserved.unwrap_or(0);
+                Some(policy)
+            }
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.channels.keys().chain(self.reserve.as_ref().map(|reserve| &reserve.platform))
+    }
+}
+
+impl Validate for AllocationConfig {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        if let Some(reserve) = &self.reserve {
+            if reserve.units < 0 {
+                issue(issues, format!("{path}.reserve.units"), "Reserved units must not be negative");
+            }
+        }
+
+        // Sorted, so that problems are reported in a stable order.
+        let mut names: Vec<&String> = self.channels.keys().collect();
+        names.sort();
+        for name in names {
+            let channel = &self.channels[name];
+            if channel.safety_buffer < 0 {
+                issue(issues, format!("{path}.channels.{name}.safety_buffer"), "Safety buffer must not be negative");
+            }
+            if let Some(share) = channel.share {
+                if !(share > 0.0 && share <= 1.0) {
+                    issue(issues, format!("{path}.channels.{name}.share"), format!("Share must be in (0, 1], got {share}"));
+                }
+            }
+            if channel.hide_below.is_some_and(|threshold| threshold <= 0) {
+                issue(issues, format!("{path}.channels.{name}.hide_below"), "Threshold must be greater than zero");
+            }
+        }
+    }
+}
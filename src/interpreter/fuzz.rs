@@ -0,0 +1,186 @@
+use std::collections::{BTreeSet, HashMap};
+use std::mem::discriminant;
+use std::time::Duration;
+use log::info;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use tai_time::MonotonicTime;
+use crate::interpreter::history::History;
+use crate::interpreter::reference::possible_values;
+use crate::intervals::Interval;
+use crate::observations::Observation;
+use crate::ordering::PlatformMetadata;
+use crate::predicates::DefinitionPredicate;
+use crate::simulation::data::SimulationMetaData;
+use crate::value::Value;
+
+pub const MAX_OBSERVATIONS: usize = 8; // Linearizations grow factorially- keep the reference tractable.
+
+pub struct FuzzParameters {
+    pub(crate) cases: u64,
+    pub(crate) max_observations: usize,
+    pub(crate) sources: usize,
+    pub(crate) seed: u64, // Case i uses seed + i, so any case can be replayed alone.
+}
+
+// A set of observations, in insertion order, on which the interpreter disagrees with (or is less complete than) the reference.
+pub struct Counterexample {
+    pub(crate) seed: u64,
+    pub(crate) initial: Value,
+    pub(crate) observations: Vec<Observation<MonotonicTime>>,
+    pub(crate) reason: String,
+}
+
+pub struct FuzzReport {
+    pub(crate) cases: u64,
+    pub(crate) unnecessary_conflicts: u64, // Interpreter conflicted though every linearization agreed- incomplete, not unsound.
+    pub(crate) counterexample: Option<Counterexample>, // Shrunk.
+    pub(crate) unnecessary_conflict: Option<Counterexample>, // The first found, shrunk- reported, but not a failure.
+}
+
+pub fn fuzz(params: &FuzzParameters) -> FuzzReport {
+    let mut unnecessary_conflicts = 0;
+    let mut unnecessary_conflict = None;
+    for case in 0..params.cases {
+        let seed = params.seed + case;
+        let (initial, observations) = generate(&mut StdRng::seed_from_u64(seed), params);
+        match differ(initial, &observations) {
+            Outcome::Agree => {}
+            outcome @ Outcome::UnnecessaryConflict(_) => {
+                unnecessary_conflicts += 1;
+                if unnecessary_conflict.is_none() {
+                    info!("Case {case} (seed {seed}) conflicts unnecessarily- shrinking {} observations", observations.len());
+                    let (initial, observations, reason) = shrink(initial, observations, &outcome);
+                    unnecessary_conflict = Some(Counterexample { seed, initial, observations, reason });
+                }
+            }
+            outcome @ Outcome::Unsound(_) => {
+                info!("Case {case} (seed {seed}) disagrees with the reference- shrinking {} observations", observations.len());
+                let (initial, observations, reason) = shrink(initial, observations, &outcome);
+                return FuzzReport {
+                    cases: case + 1,
+                    unnecessary_conflicts,
+                    counterexample: Some(Counterexample { seed, initial, observations, reason }),
+                    unnecessary_conflict,
+                };
+            }
+        }
+    }
+    FuzzReport { cases: params.cases, unnecessary_conflicts, counterexample: None, unnecessary_conflict }
+}
+
+enum Outcome {
+    Agree,
+    UnnecessaryConflict(String),
+    Unsound(String),
+}
+
+// Insert into a fresh history in order, then compare its value with every linearization's.
+fn differ(initial: Value, observations: &[Observation<MonotonicTime>]) -> Outcome {
+    let now = observations.iter().map(|o| o.interval.1).max().unwrap_or(MonotonicTime::EPOCH); // Nothing old enough to prune.
+    let mut history = History::new();
+    for observation in observations {
+        history.insert(observation.clone(), now);
+    }
+
+    let possible: BTreeSet<Option<Value>> = possible_values(observations, Some(initial));
+    match history.apply(Some(initial), now) {
+        // A found value claims every linearization reaches it.
+        Ok(found) if possible.len() == 1 && possible.contains(&Some(found)) => Outcome::Agree,
+        Ok(found) => Outcome::Unsound(format!("Interpreter found {found}, but linearizations reach {possible:?}")),
+        Err(_) if possible.len() == 1 && !possible.contains(&None) => Outcome::UnnecessaryConflict(format!("Interpreter conflicted, but every linearization reaches {possible:?}")),
+        Err(_) => Outcome::Agree,
+    }
+}
+
+// Greedily drop observations (and simplify the initial value) while the same kind of disagreement remains.
+fn shrink(mut initial: Value, mut observations: Vec<Observation<MonotonicTime>>, kind: &Outcome) -> (Value, Vec<Observation<MonotonicTime>>, String) {
+    let persists = |initial: Value, observations: &[Observation<MonotonicTime>]| discriminant(&differ(initial, observations)) == discriminant(kind);
+    let mut shrunk = true;
+    while shrunk {
+        shrunk = false;
+        for i in 0..observations.len() {
+            let mut fewer = observations.clone();
+            fewer.remove(i);
+            if persists(initial, &fewer) {
+                observations = fewer;
+                shrunk = true;
+                break;
+            }
+        }
+        if initial != 0 {
+            if persists(0, &observations) {
+                initial = 0;
+                shrunk = true;
+            }
+        }
+    }
+    match differ(initial, &observations) {
+        Outcome::UnnecessaryConflict(reason) | Outcome::Unsound(reason) => (initial, observations, reason),
+        Outcome::Agree => unreachable!() // Only shrunk while the disagreement remains.
+    }
+}
+
+fn generate(rng: &mut StdRng, params: &FuzzParameters) -> (Value, Vec<Observation<MonotonicTime>>) {
+    let count = rng.random_range(1..=params.max_observations.clamp(1, MAX_OBSERVATIONS));
+    let mut observations: Vec<Observation<MonotonicTime>> = (0..count).map(|_| {
+        // Whole milliseconds, so interval endpoints often coincide.
+        let start = rng.random_range(0..10_000);
+        let length = rng.random_range(0..3_000);
+        Observation {
+            interval: Interval(millis(start), millis(start + length)),
+            definition_predicate: predicate(rng),
+            source: format!("Fuzz{}", rng.random_range(0..params.sources.max(1))),
+            platform_metadata: PlatformMetadata::Simulation(SimulationMetaData { monotonic: 0, truth: 0..0 }),
+        }
+    }).collect();
+
+    // Per-source metadata follows interval starts, as a real interface's would- so the two never contradict.
+    observations.sort_by(|a, b| a.interval.0.cmp(&b.interval.0));
+    let mut next: HashMap<String, u64> = HashMap::new();
+    for observation in &mut observations {
+        let monotonic = next.entry(observation.source.clone()).or_default();
+        observation.platform_metadata = PlatformMetadata::Simulation(SimulationMetaData { monotonic: *monotonic, truth: 0..0 });
+        *monotonic += 1;
+    }
+
+    // But they may arrive in any order.
+    observations.shuffle(rng);
+    (rng.random_range(0..=5), observations)
+}
+
+fn predicate(rng: &mut StdRng) -> DefinitionPredicate {
+    match rng.random_range(0..10) {
+        0..=3 => DefinitionPredicate::AllMut(rng.random_range(-2..=2)),
+        4..=6 => DefinitionPredicate::Transition(rng.random_range(0..=5), rng.random_range(0..=5)),
+        7..=8 => DefinitionPredicate::LastAssn(rng.random_range(0..=5)),
+        _ => DefinitionPredicate::Unknown,
+    }
+}
+
+fn millis(ms: u64) -> MonotonicTime {
+    MonotonicTime::EPOCH + Duration::from_millis(ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agrees_with_reference() {
+        let params = FuzzParameters { cases: 300, max_observations: 6, sources: 3, seed: 0 };
+        let report = fuzz(&params);
+        if let Some(counterexample) = report.counterexample {
+            panic!("Seed {}: {}", counterexample.seed, counterexample.reason);
+        }
+        assert_eq!(report.cases, params.cases);
+
+        // Any unnecessary conflict is shrunk, but stays one.
+        if let Some(conflict) = report.unnecessary_conflict {
+            let (_, observations) = generate(&mut StdRng::seed_from_u64(conflict.seed), &params);
+            assert!(conflict.observations.len() <= observations.len());
+            assert!(matches!(differ(conflict.initial, &conflict.observations), Outcome::UnnecessaryConflict(_)));
+        }
+    }
+}
@@ -1,19 +1,35 @@
 use std::collections::LinkedList;
 use std::fmt::Debug;
+use std::sync::Arc;
 use crate::interpreter::error::ConflictError;
 use crate::interpreter::regions::Region;
+use crate::interpreter::registry::PlatformRegistry;
 use Clone;
 use crate::value::Value;
 
 #[derive(Debug)]
 pub struct History<T: PartialOrd + Clone> {
     pub(crate) list: LinkedList<Region<T>>,
+    pub(crate) registry: Arc<PlatformRegistry>, // Priority and trust of each source, used when merging.
 }
 
 impl<T: PartialOrd + Clone + Debug> History<T> {
     pub fn new() -> Self {
+        Self::with_registry(Arc::new(PlatformRegistry::default()))
+    }
+
+    pub fn with_registry(registry: Arc<PlatformRegistry>) -> Self {
         Self {
             list: LinkedList::new(),
+            registry,
+        }
+    }
+
+    // History of regions pruned from this one- merged with the same registry.
+    pub fn pruned(&self, regions: Vec<Region<T>>) -> Self {
+        Self {
+            list: LinkedList::from_iter(regions),
+            registry: self.registry.clone(),
         }
     }
 
@@ -24,7 +40,7 @@ impl<T: PartialOrd + Clone + Debug> History<T> {
         // Iterate over each region.
         for region in &mut self.list {
             // Attempt to apply.
-            let result = region.apply(value);
+            let result = region.apply(value, &self.registry);
 
             // If this is the root of the conflict- i.e. where it occurred. Then track.
             if result.is_none() && conflict_region.is_none() {
@@ -1,15 +1,32 @@
+use crate::interpreter::registry::{PlatformRegistry, Trust};
 use crate::observations::Observation;
 use crate::predicates::DefinitionPredicate;
 use crate::predicates::DefinitionPredicate::Unknown;
+use crate::value::Value;
+
+pub fn merge_procedure<T: PartialOrd + Clone>(observations: &Vec<Observation<T>>, registry: &PlatformRegistry) -> DefinitionPredicate {
+    // Low trust assignments are ignored beside fully trusted ones- mutations all happened, so are always kept.
+    let is_assignment = |o: &Observation<T>| matches!(o.definition_predicate, DefinitionPredicate::LastAssn(_));
+    let any_trusted = observations.iter().any(|o| is_assignment(o) && registry.profile(&o.source).trust == Trust::Full);
+    let observations: Vec<&Observation<T>> = observations.iter()
+        .filter(|o| !any_trusted || !is_assignment(o) || registry.profile(&o.source).trust == Trust::Full)
+        .collect();
+
+    // An authoritative assignment overrides everything concurrent with it.
+    let authoritative = observations.iter().copied().filter(|o| registry.profile(&o.source).authoritative);
+    if let Some(assignment) = preferred_assignment(authoritative, registry) {
+        return assignment;
+    }
 
-pub fn merge_procedure<T: PartialOrd + Clone>(observations: &Vec<Observation<T>>) -> DefinitionPredicate {
     let mut all_mutations = true;
+    let mut all_assignments = true;
     let mut sum = 0;
 
-    for observation in observations {
+    for observation in &observations {
         match observation.definition_predicate {
             DefinitionPredicate::AllMut(delta) if all_mutations => {
-                sum += delta
+                sum += delta;
+                all_assignments = false;
             },
             DefinitionPredicate::AllMut(_) if !all_mutations => return Unknown, // Mutations only commute with mutations.
             DefinitionPredicate::LastAssn(_) => {
@@ -21,7 +38,129 @@ pub fn merge_procedure<T: PartialOrd + Clone>(observations: &Vec<Observation<T>>
 
     if all_mutations {
         return DefinitionPredicate::AllMut(sum);
+    } else if all_assignments {
+        // Concurrent assignments- the highest priority source wins.
+        return preferred_assignment(observations.into_iter(), registry).unwrap_or(Unknown);
     } else {
-        return Unknown // TODO: LastAssn Merge.
+        return Unknown
+    }
+}
+
+// Assignment from the highest priority source- if there is one, and all of that priority agree.
+fn preferred_assignment<'a, T: PartialOrd + Clone + 'a>(observations: impl Iterator<Item = &'a Observation<T>>, registry: &PlatformRegistry) -> Option<DefinitionPredicate> {
+    let assignments: Vec<(u32, Value)> = observations
+        .filter_map(|o| match o.definition_predicate {
+            DefinitionPredicate::LastAssn(value) => Some((registry.profile(&o.source).priority, value)),
+            _ => None,
+        })
+        .collect();
+
+    let top = assignments.iter().map(|(priority, _)| *priority).max()?;
+    let mut values = assignments.iter().filter(|(priority, _)| *priority == top).map(|(_, value)| *value);
+    let value = values.next()?;
+    values.all(|other| other == value).then_some(DefinitionPredicate::LastAssn(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::interpreter::registry::SourceProfile;
+    use crate::intervals::Interval;
+    use crate::ordering::PlatformMetadata;
+    use crate::simulation::data::SimulationMetaData;
+    use crate::validation::Validate;
+    use super::*;
+
+    fn observation(source: &str, definition_predicate: DefinitionPredicate) -> Observation<u64> {
+        Observation {
+            interval: Interval(0, 10), // All concurrent.
+            definition_predicate,
+            source: source.to_string(),
+            platform_metadata: PlatformMetadata::Simulation(SimulationMetaData { monotonic: 0, truth: 0..0 }),
+        }
+    }
+
+    fn registry(profiles: &[(&str, u32, Trust, bool)]) -> PlatformRegistry {
+        PlatformRegistry {
+            sources: profiles.iter()
+                .map(|(name, priority, trust, authoritative)| (name.to_string(), SourceProfile { priority: *priority, trust: *trust, authoritative: *authoritative }))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    fn merged(observations: Vec<Observation<u64>>, registry: &PlatformRegistry) -> DefinitionPredicate {
+        merge_procedure(&observations, registry)
+    }
+
+    #[test]
+    fn sums_mutations() {
+        let observations = vec![observation("A", DefinitionPredicate::AllMut(-1)), observation("B", DefinitionPredicate::AllMut(-2))];
+        assert!(matches!(merged(observations, &PlatformRegistry::default()), DefinitionPredicate::AllMut(-3)));
+    }
+
+    #[test]
+    fn mixed_predicates_are_unknown() {
+        let observations = vec![observation("A", DefinitionPredicate::AllMut(-1)), observation("B", DefinitionPredicate::LastAssn(5))];
+        assert!(matches!(merged(observations, &PlatformRegistry::default()), Unknown));
+        let observations = vec![observation("A", DefinitionPredicate::Transition(1, 2)), observation("B", DefinitionPredicate::LastAssn(5))];
+        assert!(matches!(merged(observations, &PlatformRegistry::default()), Unknown));
+    }
+
+    #[test]
+    fn low_trust_assignments_drop_beside_full_trust() {
+        let registry = registry(&[("Marketplace", 5, Trust::Low, false)]);
+        let observations = vec![observation("Marketplace", DefinitionPredicate::LastAssn(9)), observation("Shop", DefinitionPredicate::LastAssn(4))];
+        assert!(matches!(merged(observations, &registry), DefinitionPredicate::LastAssn(4)), "Despite its higher priority");
+    }
+
+    #[test]
+    fn low_trust_assignments_stand_alone() {
+        let registry = registry(&[("A", 0, Trust::Low, false), ("B", 1, Trust::Low, false)]);
+        let observations = vec![observation("A", DefinitionPredicate::LastAssn(9)), observation("B", DefinitionPredicate::LastAssn(4))];
+        assert!(matches!(merged(observations, &registry), DefinitionPredicate::LastAssn(4)));
+    }
+
+    #[test]
+    fn authoritative_assignment_overrides() {
+        let registry = registry(&[("POS", 0, Trust::Full, true), ("Shop", 5, Trust::Full, false)]);
+        let observations = vec![
+            observation("POS", DefinitionPredicate::LastAssn(7)),
+            observation("Shop", DefinitionPredicate::LastAssn(4)),
+            observation("Vendor", DefinitionPredicate::AllMut(-1)),
+        ];
+        assert!(matches!(merged(observations, &registry), DefinitionPredicate::LastAssn(7)), "Even beside mutations");
+    }
+
+    #[test]
+    fn highest_priority_wins_where_it_agrees() {
+        let registry = registry(&[("A", 2, Trust::Full, false), ("B", 2, Trust::Full, false), ("C", 1, Trust::Full, false)]);
+        let agreeing = vec![
+            observation("A", DefinitionPredicate::LastAssn(3)),
+            observation("B", DefinitionPredicate::LastAssn(3)),
+            observation("C", DefinitionPredicate::LastAssn(8)),
+        ];
+        assert!(matches!(merged(agreeing, &registry), DefinitionPredicate::LastAssn(3)));
+
+        let disagreeing = vec![
+            observation("A", DefinitionPredicate::LastAssn(3)),
+            observation("B", DefinitionPredicate::LastAssn(4)),
+            observation("C", DefinitionPredicate::LastAssn(8)),
+        ];
+        assert!(matches!(merged(disagreeing, &registry), Unknown));
+    }
+
+    #[test]
+    fn registry_rejects_conflicting_authorities() {
+        let mut issues = vec![];
+        registry(&[("POS", 1, Trust::Full, true), ("Recount", 1, Trust::Full, true)]).validate("$.registry", &mut issues);
+        assert_eq!(issues.len(), 1, "Equal priority: {issues:?}");
+
+        let mut issues = vec![];
+        registry(&[("POS", 1, Trust::Low, true)]).validate("$.registry", &mut issues);
+        assert_eq!(issues.len(), 1, "Low trust: {issues:?}");
+
+        let mut issues = vec![];
+        registry(&[("POS", 2, Trust::Full, true), ("Recount", 1, Trust::Full, true), ("Shop", 1, Trust::Low, false)]).validate("$.registry", &mut issues);
+        assert!(issues.is_empty(), "{issues:?}");
     }
 }
@@ -3,3 +3,6 @@ pub mod regions;
 pub mod automata;
 pub mod merge;
 pub mod error;
+pub mod reference;
+pub mod registry;
+pub mod fuzz;
@@ -0,0 +1,93 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use crate::observations::Observation;
+use crate::value::Value;
+
+// Brute-force reference interpreter- an oracle for differential testing, far too slow for anything else.
+// Enumerates every linearization of the observations consistent with their intervals and per-source metadata,
+// and applies each in turn to the initial value.
+pub fn possible_values<T: PartialOrd + Clone>(observations: &[Observation<T>], initial: Option<Value>) -> BTreeSet<Option<Value>> {
+    let mut values = BTreeSet::new();
+    let mut remaining: Vec<usize> = (0..observations.len()).collect();
+    linearize(observations, &mut remaining, initial, &mut values);
+    values
+}
+
+// Depth-first over every observation which may come next- those with no remaining predecessor.
+fn linearize<T: PartialOrd + Clone>(observations: &[Observation<T>], remaining: &mut Vec<usize>, value: Option<Value>, values: &mut BTreeSet<Option<Value>>) {
+    if remaining.is_empty() {
+        values.insert(value);
+        return;
+    }
+
+    for i in 0..remaining.len() {
+        let candidate = &observations[remaining[i]];
+        let has_predecessor = remaining.iter()
+            .any(|other| observations[*other].partial_cmp(candidate) == Some(Ordering::Less));
+        if has_predecessor {
+            continue;
+        }
+
+        let next = candidate.definition_predicate.apply(value);
+        let taken = remaining.remove(i);
+        linearize(observations, remaining, next, values);
+        remaining.insert(i, taken);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::intervals::Interval;
+    use crate::ordering::PlatformMetadata;
+    use crate::predicates::DefinitionPredicate;
+    use crate::simulation::data::SimulationMetaData;
+    use super::*;
+
+    fn observation(source: &str, monotonic: u64, interval: (u64, u64), definition_predicate: DefinitionPredicate) -> Observation<u64> {
+        Observation {
+            interval: Interval(interval.0, interval.1),
+            definition_predicate,
+            source: source.to_string(),
+            platform_metadata: PlatformMetadata::Simulation(SimulationMetaData { monotonic, truth: 0..0 }),
+        }
+    }
+
+    #[test]
+    fn concurrent_assignments_reach_either() {
+        let observations = [
+            observation("A", 0, (0, 10), DefinitionPredicate::LastAssn(1)),
+            observation("B", 0, (5, 15), DefinitionPredicate::LastAssn(2)),
+        ];
+        assert_eq!(possible_values(&observations, Some(0)), BTreeSet::from([Some(1), Some(2)]));
+    }
+
+    #[test]
+    fn ordered_mutations_reach_one_value() {
+        let observations = [
+            observation("A", 0, (20, 30), DefinitionPredicate::AllMut(-3)),
+            observation("B", 0, (0, 10), DefinitionPredicate::AllMut(1)),
+            observation("A", 1, (40, 50), DefinitionPredicate::AllMut(2)),
+        ];
+        assert_eq!(possible_values(&observations, Some(5)), BTreeSet::from([Some(5)]));
+    }
+
+    #[test]
+    fn same_source_follows_metadata() {
+        // Overlapping intervals- only the source's own ordering separates them.
+        let observations = [
+            observation("A", 1, (0, 10), DefinitionPredicate::LastAssn(2)),
+            observation("A", 0, (5, 15), DefinitionPredicate::LastAssn(1)),
+        ];
+        assert_eq!(possible_values(&observations, Some(0)), BTreeSet::from([Some(2)]));
+    }
+
+    #[test]
+    fn transitions_depend_on_order() {
+        let observations = [
+            observation("A", 0, (0, 10), DefinitionPredicate::Transition(0, 3)),
+            observation("B", 0, (5, 15), DefinitionPredicate::AllMut(1)),
+        ];
+        assert_eq!(possible_values(&observations, Some(0)), BTreeSet::from([Some(4), None]));
+        assert_eq!(possible_values::<u64>(&[], None), BTreeSet::from([None]));
+    }
+}
@@ -1,5 +1,6 @@
 use std::cmp::Ordering;
 use crate::interpreter::merge::merge_procedure;
+use crate::interpreter::registry::PlatformRegistry;
 use crate::observations::Observation;
 use crate::predicates::DefinitionPredicate;
 use crate::value::Value;
@@ -31,7 +32,7 @@ impl<T: PartialOrd + Clone> Region<T> {
         self.cached_definition = None;
     }
 
-    pub(crate) fn apply(&mut self, value: Option<Value>) -> Option<Value> {
+    pub(crate) fn apply(&mut self, value: Option<Value>, registry: &PlatformRegistry) -> Option<Value> {
         match self.cached_definition.as_ref() {
             // If some definition cached, apply it.
             Some(definition) => definition.apply(value),
@@ -39,7 +40,7 @@ impl<T: PartialOrd + Clone> Region<T> {
                 // If no definition cached, must have had insert- n > 1.
                 // Therefore, apply merge procedure.
                 // If un-mergeable, returns Unknown which applied gives None
-                let merged = merge_procedure(&self.observations); // TODO: Wire Up Merge Procedure.
+                let merged = merge_procedure(&self.observations, registry);
                 self.cached_definition = Some(merged.clone());
                 merged.apply(value)
             }
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::validation::{issue, Validate, ValidationIssue};
+
+// How far a source is believed when it is concurrent with others.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trust {
+    #[default]
+    Full,
+    Low, // Assignments ignored when merged with fully trusted ones- e.g. a marketplace's delayed sync. Mutations are always kept.
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SourceProfile {
+    #[serde(default)]
+    pub(crate) priority: u32, // Higher wins between concurrent assignments.
+    #[serde(default)]
+    pub(crate) trust: Trust,
+    #[serde(default)]
+    pub(crate) authoritative: bool, // Assignments from this source override everything concurrent with them- e.g. a POS recount.
+}
+
+// Per-source priority and trust, shared by the simulator and real-world interpreters.
+// Keyed by `Observation::source`- unlisted sources are fully trusted, lowest priority and not authoritative.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PlatformRegistry {
+    pub(crate) sources: HashMap<String, SourceProfile>,
+}
+
+impl PlatformRegistry {
+    pub fn profile(&self, source: &str) -> SourceProfile {
+        self.sources.get(source).cloned().unwrap_or_default()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.sources.keys()
+    }
+}
+
+impl Validate for PlatformRegistry {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        // Two authoritative sources of equal priority can only conflict.
+        let mut authoritative: Vec<(&String, u32)> = self.sources.iter()
+            .filter(|(_, profile)| profile.authoritative)
+            .map(|(name, profile)| (name, profile.priority))
+            .collect();
+        authoritative.sort();
+        for (i, (name, priority)) in authoritative.iter().enumerate() {
+            if authoritative[..i].iter().any(|(_, other)| other == priority) {
+                issue(issues, format!("{path}.{name}.priority"), format!("Another authoritative source has priority {priority}"));
+            }
+            if self.sources[*name].trust == Trust::Low {
+                issue(issues, format!("{path}.{name}.trust"), "Authoritative sources must be fully trusted");
+            }
+        }
+    }
+}
@@ -2,7 +2,12 @@
 #![feature(future_join)]
 
 use std::{fs};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+use rand::Rng;
+use tai_time::MonotonicTime;
+use tokio::net::TcpListener;
 use log::LevelFilter;
 use log4rs::append::file::FileAppender;
 use log4rs::encode::pattern::PatternEncoder;
@@ -11,11 +16,21 @@ use log4rs::config::Config as LogConfig;
 use log4rs::Handle;
 use log::info;
 use crate::config::Config;
+use crate::interpreter::fuzz::{fuzz, Counterexample, FuzzParameters};
 use clap::{command, Parser, Subcommand};
 use crate::real_world::config::RealWorldConfig;
 use crate::real_world::real_world_main;
+use crate::real_world::shopify::mock::MockShopify;
+use crate::real_world::square::webhook::{sign, SIGNATURE_HEADER};
+use crate::real_world::woocommerce::mock::MockWooCommerce;
+use crate::simulation::config::SimulationConfig;
+use crate::simulation::compare::{compare, comparison_table};
+use crate::simulation::results::{summary_table, ScenarioSummary};
+use crate::simulation::statistics::StoppingRule;
+use crate::simulation::timeseries::{TimeSeriesFormat, TimeSeriesWriter};
 
 mod value;
+mod allocation;
 mod observations;
 mod intervals;
 mod interpreter;
@@ -25,9 +40,12 @@ mod predicates;
 mod ordering;
 mod config;
 mod simulations;
+mod validation;
 
-fn output_path(output_folder: &PathBuf, input_path: &PathBuf) -> PathBuf {
-    let candidate = output_folder.join(input_path.file_stem().unwrap()).with_extension("log");
+const CHECK_SEED: u64 = 0; // Seed for checked scenarios which do not fix their own.
+
+fn output_path(output_folder: &PathBuf, name: &str) -> PathBuf {
+    let candidate = output_folder.join(name).with_extension("log");
     return resolve_unique_path(&candidate);
 }
 
@@ -74,18 +92,20 @@ macro_rules! make_observation {
 
 
 
-/// Simple program to greet a person
-async fn simulate(input_path: PathBuf, log_handle: &mut Option<Handle>, iterations: u64) {
-    // Generate output path (+1 each time)
-    let output_path = output_path(&PathBuf::from("output"), &input_path);
-    info!("Loading Simulation from: {input_path:?}");
-    info!("Will output to {output_path:?}");
-
+fn timeseries_path(output_folder: &PathBuf, scenario: &str, format: TimeSeriesFormat) -> PathBuf {
+    let candidate = output_folder.join(format!("{scenario}_timeseries")).with_extension(match format {
+        TimeSeriesFormat::Csv => "csv",
+        TimeSeriesFormat::Jsonl => "jsonl",
+    });
+    return resolve_unique_path(&candidate);
+}
 
+// Point the logger at a file- appending if it already exists.
+fn set_log_file(log_handle: &mut Option<Handle>, path: &PathBuf) {
     // Initialise output logfile
     let logfile = FileAppender::builder()
         .encoder(Box::new(PatternEncoder::new("{l} - {m}\n")))
-        .build(output_path).unwrap();
+        .build(path).unwrap();
 
     let logconfig = LogConfig::builder()
         .appender(Appender::builder().build("logfile", Box::new(logfile)))
@@ -99,6 +119,35 @@ async fn simulate(input_path: PathBuf, log_handle: &mut Option<Handle>, iteratio
     } else {
         log_handle.replace(log4rs::init_config(logconfig).unwrap());
     }
+}
+
+// Distinct name for each simulation in a file- configured, or derived from the file name.
+fn scenario_names(stem: &str, cfgs: &Vec<SimulationConfig>) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(cfgs.len());
+    for (i, cfg) in cfgs.iter().enumerate() {
+        let mut name = match &cfg.name {
+            Some(name) => name.clone(),
+            None if cfgs.len() == 1 => stem.to_string(),
+            None => format!("{stem}_{i}"),
+        };
+        if names.contains(&name) {
+            name = format!("{name}_{i}");
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// Simple program to greet a person
+async fn simulate(input_path: PathBuf, log_handle: &mut Option<Handle>, iterations: u64, stopping: Option<StoppingRule>, timeseries: Option<TimeSeriesFormat>, soundness: bool) {
+    let output_folder = PathBuf::from("output");
+    let stem = input_path.file_stem().unwrap().to_string_lossy().to_string();
+
+    // Generate output path (+1 each time)
+    let file_log = output_path(&output_folder, &stem);
+    set_log_file(log_handle, &file_log);
+    info!("Loading Simulation from: {input_path:?}");
+    info!("Will output to {file_log:?}");
 
     // Read and parse
     let contents = fs::read_to_string(&input_path).unwrap();
@@ -107,31 +156,81 @@ async fn simulate(input_path: PathBuf, log_handle: &mut Option<Handle>, iteratio
         .expect(&*("Failed to parse config ".to_owned() + input_path.display().to_string().as_str()));
 
     // Only run simulation if config matches
-    if let Config::Simulation(cfg) = config {
-        if let Some(first_cfg) = cfg.first() {
-            let results = simulation::driver::driver(first_cfg.clone(), iterations);
-            log::info!("Ended With Results {results:#?}");
-            log::info!("Finished processing {}", input_path.display());
-        } else {
+    if let Config::Simulation(cfgs) = config {
+        if cfgs.is_empty() {
             log::warn!("Simulation config in {} was empty", input_path.display());
+            return;
+        }
+
+        let names = scenario_names(&stem, &cfgs);
+        info!("Found {} simulations: {names:?}", cfgs.len());
+
+        let mut all_results = Vec::with_capacity(cfgs.len());
+        for (name, mut cfg) in names.into_iter().zip(cfgs) {
+            cfg.soundness |= soundness;
+
+            // Each simulation logs to its own file, unless it is the only one.
+            let scenario_log = if all_results.is_empty() && name == stem {
+                file_log.clone()
+            } else {
+                output_path(&output_folder, &name)
+            };
+            set_log_file(log_handle, &scenario_log);
+            info!("Running Simulation {name} from: {input_path:?}");
+            println!("Running Simulation {name}");
+
+            // Optionally export each iteration's trajectory.
+            let mut timeseries_writer = timeseries.map(|format| {
+                let path = timeseries_path(&output_folder, &name, format);
+                info!("Will export time series to {path:?}");
+                TimeSeriesWriter::create(&path, format, name.clone(), &cfg)
+            });
+
+            let results = simulation::driver::driver(cfg, iterations, stopping, timeseries_writer.as_mut());
+            log::info!("Ended With Results {results:#?}");
+
+            // Results section in the file's log.
+            if scenario_log != file_log {
+                set_log_file(log_handle, &file_log);
+                log::info!("Simulation {name} (see {scenario_log:?}) Ended With Results {results:#?}");
+            }
+            all_results.push((name, results));
         }
+
+        // Compare all simulations in the file side by side.
+        let summary = summary_table(&all_results);
+        log::info!("Summary:\n{summary}");
+        println!("{summary}");
+
+        // Keep results for later comparison.
+        let results_path = resolve_unique_path(&output_folder.join(format!("{stem}_results")).with_extension("json"));
+        let summaries: Vec<ScenarioSummary> = all_results.iter()
+            .map(|(name, results)| ScenarioSummary::new(name.clone(), results))
+            .collect();
+        fs::write(&results_path, serde_json::to_string_pretty(&summaries).unwrap()).unwrap();
+        log::info!("Results written to {results_path:?}");
+        log::info!("Finished processing {}", input_path.display());
     } else {
         log::warn!("Config in {} was not a Simulation variant", input_path.display());
     }
 
 }
 
-async fn command_simulate(input_path: PathBuf, iterations: u64) {
+// Simulate every config at the path- or none, returning false, if any is invalid.
+async fn command_simulate(input_path: PathBuf, iterations: u64, stopping: Option<StoppingRule>, timeseries: Option<TimeSeriesFormat>, soundness: bool) -> bool {
+    let files: Vec<PathBuf> = if input_path.is_dir() {
+        fs::read_dir(input_path.clone()).unwrap().map(|entry| entry.unwrap().path()).collect()
+    } else {
+        vec![input_path]
+    };
+    if !report_invalid(&files) {
+        return false;
+    }
+
     fs::create_dir_all("output").unwrap();
     let mut log_handle: Option<Handle> = None;
-    if input_path.is_dir() {
-        for entry in fs::read_dir(input_path.clone()).unwrap() {
-            let entry = entry.unwrap();
-            let path = entry.path();
-            simulate(path, &mut log_handle, iterations).await;
-        }
-    } else {
-        simulate(input_path, &mut log_handle, iterations).await;
+    for path in files {
+        simulate(path, &mut log_handle, iterations, stopping, timeseries, soundness).await;
     }
 
     let input_dirs = vec!["scenarios/todo"];
@@ -174,6 +273,197 @@ async fn command_simulate(input_path: PathBuf, iterations: u64) {
             }
         }
     }
+    true
+}
+
+// Every config in a directory (and its subdirectories) in a stable order, or the single config given.
+fn config_files(input_path: &PathBuf) -> Vec<PathBuf> {
+    if !input_path.is_dir() {
+        return vec![input_path.clone()];
+    }
+
+    let mut files = vec![];
+    for entry in fs::read_dir(input_path).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(config_files(&path));
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+// Report every problem in the configs, before any runs- returning whether all were valid.
+fn report_invalid(files: &[PathBuf]) -> bool {
+    let mut valid = true;
+    for file in files {
+        let issues = validation::validate_file(file).unwrap_or_else(|e| vec![validation::ValidationIssue { path: "$".to_string(), message: e }]);
+        for issue in issues {
+            valid = false;
+            eprintln!("{}: {}", file.display(), issue);
+        }
+    }
+    valid
+}
+
+// Report every problem in every config, returning whether all were valid.
+fn command_validate(input_path: &PathBuf) -> bool {
+    let mut valid = true;
+    for file in config_files(input_path) {
+        match validation::validate_file(&file) {
+            Ok(issues) if issues.is_empty() => println!("{}: OK", file.display()),
+            Ok(issues) => {
+                valid = false;
+                for issue in issues {
+                    println!("{}: {}", file.display(), issue);
+                }
+            }
+            Err(e) => {
+                valid = false;
+                println!("{}: {}", file.display(), e);
+            }
+        }
+    }
+    valid
+}
+
+// Config stem and run number of a results file- <stem>_results.json is run 0, and resolve_unique_path names later runs <stem>_results_<n>.json.
+fn results_run(path: &Path) -> Option<(String, u64)> {
+    let name = path.file_name()?.to_string_lossy().strip_suffix(".json")?.to_string();
+    if let Some(stem) = name.strip_suffix("_results") {
+        return Some((stem.to_string(), 0));
+    }
+    let (prefix, run) = name.rsplit_once('_')?;
+    let run = run.parse::<u64>().ok()?;
+    Some((prefix.strip_suffix("_results")?.to_string(), run))
+}
+
+// Scenario results from a results file, or the newest results file of each config in a directory.
+fn load_summaries(path: &PathBuf) -> Vec<ScenarioSummary> {
+    let files: Vec<PathBuf> = if path.is_dir() {
+        let mut newest: BTreeMap<String, (u64, PathBuf)> = BTreeMap::new();
+        for file in fs::read_dir(path).unwrap().map(|entry| entry.unwrap().path()) {
+            let Some((stem, run)) = results_run(&file) else {
+                continue;
+            };
+            if newest.get(&stem).is_none_or(|(latest, _)| run > *latest) {
+                newest.insert(stem, (run, file));
+            }
+        }
+        newest.into_values().map(|(_, file)| {
+            info!("Comparing results from {}", file.display());
+            file
+        }).collect()
+    } else {
+        vec![path.clone()]
+    };
+
+    files.iter().flat_map(|file| {
+        let contents = fs::read_to_string(file).unwrap();
+        serde_json::from_str::<Vec<ScenarioSummary>>(&contents)
+            .expect(&*("Failed to parse results ".to_owned() + file.display().to_string().as_str()))
+    }).collect()
+}
+
+// Diff two runs of the same scenarios, returning whether the candidate has no regressions.
+fn command_compare(baseline: &PathBuf, candidate: &PathBuf) -> bool {
+    let (comparisons, unmatched) = compare(&load_summaries(baseline), &load_summaries(candidate));
+    println!("{}", comparison_table(&comparisons));
+    for name in unmatched {
+        println!("Scenario {name} is only in one run- not compared");
+    }
+
+    let regressions: Vec<&str> = comparisons.iter().filter(|c| c.regressed()).map(|c| c.scenario.as_str()).collect();
+    if regressions.is_empty() {
+        println!("No significant regressions");
+        true
+    } else {
+        println!("REGRESSIONS in {}: {}", regressions.len(), regressions.join(", "));
+        false
+    }
+}
+
+// Run every scenario with an `expect` block on fixed seeds, returning whether all expectations held- none run if any config is invalid.
+fn command_check(input_path: &PathBuf, iterations: u64) -> bool {
+    if !report_invalid(&config_files(input_path)) {
+        return false;
+    }
+    let output_folder = PathBuf::from("output");
+    fs::create_dir_all(&output_folder).unwrap();
+    let mut log_handle: Option<Handle> = None;
+
+    let mut passed = true;
+    for file in config_files(input_path) {
+        let contents = fs::read_to_string(&file).unwrap();
+        let cfgs = match serde_json::from_str::<Config>(&contents) {
+            Ok(Config::Simulation(cfgs)) => cfgs,
+            Ok(Config::RealWorld(_)) => continue, // Nothing to check.
+            Err(e) => {
+                passed = false;
+                println!("FAIL {}: Failed to parse: {e}", file.display());
+                continue;
+            }
+        };
+
+        let stem = file.file_stem().unwrap().to_string_lossy().to_string();
+        for (name, mut cfg) in scenario_names(&stem, &cfgs).into_iter().zip(cfgs) {
+            let Some(expect) = cfg.expect.clone() else {
+                println!("SKIP {name}: no expectations");
+                continue;
+            };
+            cfg.seed = Some(cfg.seed.unwrap_or(CHECK_SEED));
+
+            let scenario_log = output_path(&output_folder, &format!("check_{name}"));
+            set_log_file(&mut log_handle, &scenario_log);
+            info!("Checking Simulation {name} from: {file:?}");
+
+            let results = simulation::driver::driver(cfg, iterations, None, None);
+            info!("Ended With Results {results:#?}");
+
+            let violations = expect.check(&results);
+            if violations.is_empty() {
+                println!("PASS {name}");
+            } else {
+                passed = false;
+                println!("FAIL {name} (see {scenario_log:?})");
+                for violation in violations {
+                    info!("Violated: {violation}");
+                    println!("    {violation}");
+                }
+            }
+        }
+    }
+    passed
+}
+
+// Differential test of the interpreter- prints a shrunk counterexample on disagreement.
+fn command_fuzz(params: &FuzzParameters) -> bool {
+    colog::init();
+    info!("Fuzzing interpreter from seed {}", params.seed);
+    let report = fuzz(params);
+    println!("{} cases, {} unnecessary conflicts", report.cases, report.unnecessary_conflicts);
+    if let Some(conflict) = &report.unnecessary_conflict {
+        print_counterexample("NOTE", conflict);
+    }
+
+    let Some(counterexample) = report.counterexample else {
+        println!("PASS interpreter agrees with the reference");
+        return true;
+    };
+    print_counterexample("FAIL", &counterexample);
+    false
+}
+
+fn print_counterexample(verdict: &str, counterexample: &Counterexample) {
+    println!("{verdict} seed {} (replay with --seed {} --cases 1): {}", counterexample.seed, counterexample.seed, counterexample.reason);
+    println!("    Initial value: {}", counterexample.initial);
+    for observation in &counterexample.observations {
+        println!("    {:?} {:?} {:?}..{:?} {:?}", observation.source, observation.definition_predicate,
+            observation.interval.0.duration_since(MonotonicTime::EPOCH), observation.interval.1.duration_since(MonotonicTime::EPOCH),
+            observation.platform_metadata);
+    }
 }
 
 #[derive(Parser)]
@@ -190,8 +480,20 @@ enum Commands {
     Simulate {
         /// Path to the config or directory of config files
         input_path: PathBuf,
-        /// Number of iterations to run the simulator for each config.
-        iterations: u64
+        /// Number of iterations to run the simulator for each config- the cap, with --target-width.
+        iterations: u64,
+        /// Stop once the 95% intervals for conflict and divergence rates are narrower than this.
+        #[arg(long)]
+        target_width: Option<f64>,
+        /// With --target-width, also wait for the mean convergence time interval to be narrower than this (seconds).
+        #[arg(long, requires = "target_width")]
+        convergence_width: Option<f64>,
+        /// Export truth, interpreter and platform values per iteration in this format.
+        #[arg(long, value_enum)]
+        timeseries: Option<TimeSeriesFormat>,
+        /// Check every observation's interval and ordering against the true events it reflects.
+        #[arg(long)]
+        soundness: bool,
     },
 
     /// Run the system using a specific configuration file
@@ -199,6 +501,94 @@ enum Commands {
         /// Path to the config file
         config_file: PathBuf,
     },
+
+    /// Run scenarios with fixed seeds, failing if any `expect` block is violated
+    Check {
+        /// Path to the config or directory of config files
+        input_path: PathBuf,
+        /// Number of iterations to run for each scenario.
+        #[arg(long, default_value_t = 100)]
+        iterations: u64,
+    },
+
+    /// Compare results of two runs of the same scenarios, highlighting significant regressions
+    Compare {
+        /// Results file, or directory of results files, from the baseline run
+        baseline: PathBuf,
+        /// Results file, or directory of results files, from the candidate run
+        candidate: PathBuf,
+    },
+
+    /// Check a config or directory of config files, without running anything
+    Validate {
+        /// Path to the config or directory of config files
+        input_path: PathBuf,
+    },
+
+    /// Compare the interpreter against a brute-force reference on random observation sets
+    Fuzz {
+        /// Number of random observation sets to try.
+        #[arg(long, default_value_t = 1000)]
+        cases: u64,
+        /// Most observations in a set- at most 8, as every linearization is enumerated.
+        #[arg(long, default_value_t = 6)]
+        max_observations: usize,
+        /// Number of sources observations are drawn from.
+        #[arg(long, default_value_t = 3)]
+        sources: usize,
+        /// Seed of the first case- a random seed if not given.
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+
+    /// Serve a local mock of the Shopify inventory API, for running Shopify platforms without a store
+    MockShopify {
+        /// Address to serve on.
+        #[arg(long, default_value = "127.0.0.1:8787")]
+        listen: String,
+        /// Access token clients must send.
+        #[arg(long)]
+        token: String,
+        /// URL to send signed inventory_levels/update webhooks to.
+        #[arg(long)]
+        webhook_url: Option<String>,
+        /// Secret to sign webhooks with.
+        #[arg(long, requires = "webhook_url")]
+        webhook_secret: Option<String>,
+    },
+
+    /// Serve a local mock of the WooCommerce products and orders API, for running WooCommerce platforms without a store
+    MockWoocommerce {
+        /// Address to serve on.
+        #[arg(long, default_value = "127.0.0.1:8789")]
+        listen: String,
+        /// REST API consumer key clients must send.
+        #[arg(long)]
+        consumer_key: String,
+        /// REST API consumer secret clients must send.
+        #[arg(long)]
+        consumer_secret: String,
+        /// URL to send signed order.created and order.updated webhooks to.
+        #[arg(long)]
+        webhook_url: Option<String>,
+        /// Secret to sign webhooks with.
+        #[arg(long, requires = "webhook_url")]
+        webhook_secret: Option<String>,
+    },
+
+    /// Sign and send a saved Square webhook payload, e.g. to a platform's webhook receiver
+    ReplaySquareWebhook {
+        /// Path to the JSON payload
+        payload: PathBuf,
+        /// URL to send it to.
+        url: String,
+        /// Subscription signature key to sign it with.
+        #[arg(long)]
+        signature_key: String,
+        /// URL to sign it for, if the receiver is configured with another (e.g. behind a proxy).
+        #[arg(long)]
+        notification_url: Option<String>,
+    },
 }
 
 
@@ -207,9 +597,15 @@ async fn main() {
     let cli = Cli::parse();
 
     match &cli.command {
-        Commands::Simulate { input_path, iterations } => {
+        Commands::Simulate { input_path, iterations, target_width, convergence_width, timeseries, soundness } => {
+            let stopping = target_width.map(|rate_width| StoppingRule {
+                rate_width,
+                convergence_width: convergence_width.map(Duration::from_secs_f64),
+            });
             // Call your simulation logic here
-            command_simulate(input_path.to_owned(), iterations.to_owned()).await;
+            if !command_simulate(input_path.to_owned(), iterations.to_owned(), stopping, timeseries.to_owned(), *soundness).await {
+                std::process::exit(1);
+            }
         }
 
         Commands::Run { config_file } => {
@@ -217,6 +613,9 @@ async fn main() {
             // run(config_file);
             colog::init(); // Prefer colog to log4rs in general- but log4rs supports files.
             info!("Loading real-world config at: {:?}", config_file);
+            if !report_invalid(&[config_file.clone()]) {
+                std::process::exit(1);
+            }
             let contents = fs::read_to_string(&config_file).unwrap();
             let config: Config = serde_json::from_str(&contents)
                 .expect(&*("Failed to parse config ".to_owned() + config_file.display().to_string().as_str()));
@@ -227,6 +626,67 @@ async fn main() {
                 log::warn!("Config in {} was not a RealWorld variant", config_file.display());
             }
         }
+
+        Commands::Check { input_path, iterations } => {
+            if !command_check(input_path, *iterations) {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Compare { baseline, candidate } => {
+            if !command_compare(baseline, candidate) {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Validate { input_path } => {
+            if !command_validate(input_path) {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Fuzz { cases, max_observations, sources, seed } => {
+            let params = FuzzParameters {
+                cases: *cases,
+                max_observations: *max_observations,
+                sources: *sources,
+                seed: seed.unwrap_or_else(|| rand::rng().random()),
+            };
+            if !command_fuzz(&params) {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::MockShopify { listen, token, webhook_url, webhook_secret } => {
+            colog::init();
+            let listener = TcpListener::bind(listen).await.expect(format!("Failed to listen on {listen}").as_str());
+            let webhook = webhook_url.clone().map(|url| (url, webhook_secret.clone().unwrap_or_default()));
+            info!("Mock Shopify at http://{listen}");
+            MockShopify::new(token.clone(), webhook).run(listener).await;
+        }
+
+        Commands::MockWoocommerce { listen, consumer_key, consumer_secret, webhook_url, webhook_secret } => {
+            colog::init();
+            let listener = TcpListener::bind(listen).await.expect(format!("Failed to listen on {listen}").as_str());
+            let webhook = webhook_url.clone().map(|url| (url, webhook_secret.clone().unwrap_or_default()));
+            info!("Mock WooCommerce at http://{listen}");
+            MockWooCommerce::new(consumer_key, consumer_secret, webhook).run(listener).await;
+        }
+
+        Commands::ReplaySquareWebhook { payload, url, signature_key, notification_url } => {
+            let body = fs::read(payload).unwrap();
+            let signature = sign(signature_key, notification_url.as_ref().unwrap_or(url), &body);
+            let response = reqwest::Client::new().post(url)
+                .header(SIGNATURE_HEADER, signature)
+                .header("Content-Type", "application/json")
+                .body(body)
+                .send().await
+                .expect(format!("Failed to send to {url}").as_str());
+            println!("{}", response.status());
+            if !response.status().is_success() {
+                std::process::exit(1);
+            }
+        }
     }
 
     // real_world_main();
@@ -1,11 +1,15 @@
 use std::cmp::Ordering;
 use crate::observations::Observation;
 use crate::simulation::data::SimulationMetaData;
+use crate::real_world::shopify::ShopifyMetadata;
 use crate::real_world::square::SquareMetadata;
+use crate::real_world::woocommerce::WooCommerceMetadata;
 
 #[derive(Clone, Debug)]
 pub enum PlatformMetadata {
     Square(SquareMetadata), // Square Logical Ordering
+    Shopify(ShopifyMetadata), // Shopify Logical Ordering
+    WooCommerce(WooCommerceMetadata), // WooCommerce Logical Ordering
     Simulation(SimulationMetaData) // Simulation Logical Ordering
 }
 
@@ -23,6 +27,16 @@ impl PartialOrd for PlatformMetadata {
                 Some(Ordering::Equal) => None,
                 x => x
             },
+            // When platforms are both shopify, defer to shopify ordering.
+            (PlatformMetadata::Shopify(a), PlatformMetadata::Shopify(b)) => match a.partial_cmp(b) {
+                Some(Ordering::Equal) => None,
+                x => x
+            },
+            // When platforms are both woocommerce, defer to woocommerce ordering.
+            (PlatformMetadata::WooCommerce(a), PlatformMetadata::WooCommerce(b)) => match a.partial_cmp(b) {
+                Some(Ordering::Equal) => None,
+                x => x
+            },
             // When platforms are both simulation, defer to simulation ordering.
             (PlatformMetadata::Simulation(a), PlatformMetadata::Simulation(b)) => match a.partial_cmp(b) {
                 Some(Ordering::Equal) => None,
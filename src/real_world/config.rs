@@ -1,18 +1,45 @@
+use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
+use crate::allocation::AllocationConfig;
+use crate::interpreter::registry::PlatformRegistry;
+use crate::real_world::reconciliation::Reconciliation;
+use crate::real_world::scheduler::SchedulerConfig;
+use crate::real_world::shopify::ShopifyConfig;
 use crate::real_world::square::polling::SquarePollingConfig;
 use crate::real_world::square::record::SquareRecordConfig;
+use crate::real_world::woocommerce::WooCommerceConfig;
 use crate::value::Value;
 
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RealWorldConfig {
-    pub(crate) initial_value: Value, // Initial value to write to platforms
+    #[serde(default)]
+    pub(crate) initial_value: Option<Value>, // Stock to start from under InitialValue reconciliation- or where another cannot decide.
     pub(crate) platforms: Vec<(String,PlatformConfig)>, // Platforms to use.
+    #[serde(default)]
+    pub(crate) registry: PlatformRegistry, // Priority and trust of each platform, by name.
+    #[serde(default)]
+    pub(crate) allocation: AllocationConfig, // Between interpreted stock and each platform's written value.
+    #[serde(default)]
+    pub(crate) secrets_file: Option<PathBuf>, // Named credentials, for {"Secret": name}.
+    #[serde(default)]
+    pub(crate) scheduling: SchedulerConfig, // Pacing and retries of requests to each account.
+    #[serde(default)]
+    pub(crate) reconciliation: Option<Reconciliation>, // Where platforms disagree at startup.
+}
+
+impl RealWorldConfig {
+    // As configured- or the most recently changed platform, so live stock is only overwritten by choice.
+    pub fn reconciliation(&self) -> Reconciliation {
+        self.reconciliation.clone().unwrap_or(Reconciliation::MostRecent)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 
 pub enum PlatformConfig {
     Polling(SquarePollingConfig),
-    Records(SquareRecordConfig)
+    Records(SquareRecordConfig),
+    Shopify(ShopifyConfig),
+    WooCommerce(WooCommerceConfig)
 }
\ No newline at end of file
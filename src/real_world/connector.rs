@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::watch;
+use tokio::time::sleep;
+use crate::allocation::{allocated, ChannelAllocation};
+use crate::intervals::Interval;
+use crate::observations::Observation;
+use crate::ordering::PlatformMetadata;
+use crate::predicates::DefinitionPredicate;
+use crate::value::Value;
+
+// How a change in a polled count is interpreted.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum PollingInterpretation {
+    Transition,
+    Mutation,
+    Assignment
+}
+
+// Push platforms may redeliver a change for this long- Shopify retries for 48 hours.
+pub const REDELIVERY_WINDOW_HOURS: i64 = 48;
+
+// A change read from a platform's history- None where it should be skipped (e.g. our own writes).
+pub struct Change {
+    pub(crate) id: String, // Unique per platform, to skip changes seen in overlapping fetches.
+    pub(crate) at: DateTime<Utc>, // When the platform made it, by its clock.
+    pub(crate) observation: Option<Observation<DateTime<Utc>>>,
+}
+
+// Ids of changes made since a horizon- so overlapping fetches and redeliveries are observed once, without remembering every change.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RecentChanges {
+    seen: HashMap<String, DateTime<Utc>>, // By id, when the change was made- or last fetched, see first_sight_at.
+}
+
+impl RecentChanges {
+    // Whether the change is new- remembering it if so.
+    pub fn first_sight(&mut self, change: &Change) -> bool {
+        self.first_sight_at(change, change.at)
+    }
+
+    // As first_sight- but remembered as of another time, for platforms which fetch by something other than when a change was made.
+    pub fn first_sight_at(&mut self, change: &Change, at: DateTime<Utc>) -> bool {
+        self.seen.insert(change.id.clone(), at).is_none()
+    }
+
+    // Forget changes remembered as of before the horizon- which must never be read again.
+    pub fn forget_before(&mut self, horizon: DateTime<Utc>) {
+        self.seen.retain(|_, at| *at >= horizon);
+    }
+}
+
+// Everything the workers need from a platform- adding a channel is one impl of this.
+// Requests fail only fatally (retryable failures are retried by the scheduler)- stopping the worker, with why.
+pub trait PlatformConnector: Send + 'static {
+    type Cursor: Clone + Send;
+
+    fn name(&self) -> &str;
+
+    // Current count, with when the request was sent and replied to.
+    fn read_count(&mut self) -> impl Future<Output = Result<(Value, DateTime<Utc>, DateTime<Utc>), String>> + Send;
+
+    // Changes after the cursor, oldest first and each only once, and the cursor to fetch from next.
+    fn changes_since(&mut self, cursor: Self::Cursor) -> impl Future<Output = Result<(Vec<Change>, Self::Cursor), String>> + Send;
+
+    // When the count last changed, by the platform's clock- None where it cannot say.
+    fn last_changed(&mut self) -> impl Future<Output = Result<Option<DateTime<Utc>>, String>> + Send {
+        async { Ok(None) }
+    }
+
+    // Where changes are fetched from on start.
+    fn initial_cursor(&self) -> Self::Cursor;
+
+    // Overwrite the count- without it being read back as a change.
+    fn write(&mut self, value: Value) -> impl Future<Output = Result<(), String>> + Send;
+
+    // Platform ordering of an observation made at this (platform) time.
+    fn metadata(&self, timestamp: DateTime<Utc>) -> PlatformMetadata;
+
+    // Wait until worth reading again- platforms that push notifications may wake sooner or later than the backoff.
+    // The wait does not borrow the connector- so it runs on across the writes made meanwhile.
+    fn wait_for_changes(&mut self, backoff: Duration) -> impl Future<Output = ()> + Send + 'static {
+        sleep(backoff)
+    }
+}
+
+// Polls the count every backoff, observing each difference, and writes interpreted values in between- until a request fails fatally.
+pub async fn poll_worker<C: PlatformConnector>(
+    mut connector: C,
+    interpretation: PollingInterpretation,
+    backoff: Duration,
+    allocation: Option<ChannelAllocation>,
+    mut to_write: watch::Receiver<Option<Value>>,
+    observation_out: Sender<Observation<DateTime<Utc>>>,
+    initial_poll: (DateTime<Utc>, Value),
+    initial_offset: Value,
+) -> Result<Infallible, String> {
+    let (mut last_sent, mut last_value) = initial_poll;
+    let mut offset = initial_offset; // Stock minus the channel's value, as of the last write- translates polled values back to stock.
+
+    loop {
+        let (value, sent, replied) = connector.read_count().await?;
+        if value != last_value {
+            // Generate Observation!
+            observation_out.send(Observation {
+                interval: Interval(last_sent, replied),
+                definition_predicate: match &interpretation {
+                    PollingInterpretation::Transition => DefinitionPredicate::Transition(last_value + offset, value + offset),
+                    PollingInterpretation::Mutation => DefinitionPredicate::AllMut(value - last_value),
+                    PollingInterpretation::Assignment => DefinitionPredicate::LastAssn(value + offset),
+                },
+                source: connector.name().to_string(),
+                platform_metadata: connector.metadata(sent), // Use poll sent times as logical ordering.
+            }).await.unwrap();
+        }
+        (last_sent, last_value) = (sent, value);
+
+        // Schedule next poll- writing values as they are interpreted.
+        let wait = connector.wait_for_changes(backoff); // Once per cycle- a write does not restart it.
+        tokio::pin!(wait);
+        loop {
+            tokio::select! {
+                _ = &mut wait => break,
+                changed = to_write.changed() => {
+                    changed.unwrap();
+                    // Write it - NOTE: UNSAFE!
+                    let value = to_write.borrow_and_update().unwrap();
+                    let written = allocated(allocation.as_ref(), value);
+                    connector.write(written).await?;
+                    offset = value - written;
+                    last_value = written;
+                }
+            }
+        }
+    }
+}
+
+// Fetches changes every backoff, observing each, and writes interpreted values in between- until a request fails fatally.
+pub async fn record_worker<C: PlatformConnector>(
+    mut connector: C,
+    backoff: Duration,
+    allocation: Option<ChannelAllocation>,
+    mut to_write: watch::Receiver<Option<Value>>,
+    observation_out: Sender<Observation<DateTime<Utc>>>,
+) -> Result<Infallible, String> {
+    let mut cursor = connector.initial_cursor();
+    loop {
+        let changes;
+        (changes, cursor) = connector.changes_since(cursor).await?;
+
+        for change in changes {
+            if let Some(observation) = change.observation {
+                info!("{} - Observed New {}: {:?}!", connector.name(), change.id, observation);
+                observation_out.send(observation).await.unwrap();
+            }
+        }
+
+        // Wait before next request- writing values, as allocated to this channel, as they are interpreted.
+        let wait = connector.wait_for_changes(backoff); // Once per cycle- a write does not restart it.
+        tokio::pin!(wait);
+        loop {
+            tokio::select! {
+                _ = &mut wait => break,
+                changed = to_write.changed() => {
+                    changed.unwrap();
+                    let value = to_write.borrow_and_update().unwrap();
+                    connector.write(allocated(allocation.as_ref(), value)).await?;
+                }
+            }
+        }
+    }
+}
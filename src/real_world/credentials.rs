@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt::{Debug, Formatter};
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use crate::validation::{issue, Validate, ValidationIssue};
+
+// Where a platform credential comes from- e.g. {"Env": "VENDOR_A_TOKEN"}.
+// A bare string is still accepted, but warned of- configs are shared and logged, credentials should not be.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Credential {
+    Source(CredentialSource),
+    Inline(String),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum CredentialSource {
+    Env(String), // Name of an environment variable.
+    File(PathBuf), // File holding only the credential- e.g. a mounted secret. Surrounding whitespace is ignored.
+    Secret(String), // Name in the secrets file.
+}
+
+// Names of sources are fine to print- their values are not.
+impl Debug for Credential {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Credential::Source(source) => source.fmt(f),
+            Credential::Inline(_) => f.write_str("Inline(<redacted>)"),
+        }
+    }
+}
+
+// A resolved credential- redacted wherever it is printed.
+#[derive(Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    // Identifies the account the credential belongs to, within a run- without revealing it.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.0.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+// Named credentials, kept in a file beside (not in) the config.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Secrets(HashMap<String, Secret>);
+
+impl Secrets {
+    pub fn load(path: &Path) -> Secrets {
+        let contents = fs::read_to_string(path).expect(format!("Failed to read secrets file {}", path.display()).as_str());
+        serde_json::from_str(&contents).expect(format!("Failed to parse secrets file {}", path.display()).as_str())
+    }
+}
+
+impl Credential {
+    pub fn resolve(&self, secrets: &Secrets) -> Result<Secret, String> {
+        let value = match self {
+            Credential::Inline(value) => {
+                warn!("A credential is written in the config- prefer Env, File or Secret");
+                value.clone()
+            }
+            Credential::Source(CredentialSource::Env(name)) => env::var(name)
+                .map_err(|_| format!("Environment variable {name} is not set"))?,
+            Credential::Source(CredentialSource::File(path)) => fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read credential file {}: {e}", path.display()))?
+                .trim().to_string(),
+            Credential::Source(CredentialSource::Secret(name)) => secrets.0.get(name)
+                .map(|secret| secret.0.clone())
+                .ok_or_else(|| format!("No secret named {name}"))?,
+        };
+        match value.is_empty() {
+            true => Err(format!("Credential from {self:?} is empty")),
+            false => Ok(Secret(value)),
+        }
+    }
+}
+
+// Sources can only be checked for form here- they are read on the deployment machine.
+impl Validate for Credential {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        let empty = match self {
+            Credential::Inline(value) => value.trim().is_empty(),
+            Credential::Source(CredentialSource::Env(name)) | Credential::Source(CredentialSource::Secret(name)) => name.trim().is_empty(),
+            Credential::Source(CredentialSource::File(path)) => path.as_os_str().is_empty(),
+        };
+        if empty {
+            issue(issues, path.to_string(), "Credential must not be empty");
+        }
+    }
+}
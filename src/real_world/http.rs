@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hmac::{Hmac, Mac};
+use log::{error, info, warn};
+use sha2::Sha256;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+
+// Minimal HTTP/1.1 server- enough for webhook receivers and mock platforms, one request per connection.
+// Requests are bounded in size and time to read. Still, put it behind a TLS-terminating proxy.
+
+const MAX_BODY: usize = 1 << 20; // Webhooks are small- refuse anything larger.
+const MAX_HEAD: usize = 16 << 10; // Request line and headers, together.
+const MAX_HEADERS: usize = 64;
+const READ_TIMEOUT: Duration = Duration::from_secs(10); // For the whole request- so slow senders cannot hold a task.
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub(crate) method: String,
+    pub(crate) path: String, // Without the query.
+    pub(crate) query: HashMap<String, String>,
+    pub(crate) headers: HashMap<String, String>, // Names lowercased.
+    pub(crate) body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(|v| v.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub(crate) status: u16,
+    pub(crate) body: String, // Always JSON.
+}
+
+impl Response {
+    pub fn json(status: u16, body: impl Into<String>) -> Response {
+        Response { status, body: body.into() }
+    }
+
+    pub fn status(status: u16) -> Response {
+        Response { status, body: "{}".to_string() }
+    }
+}
+
+// Accept connections until the listener fails, handling each on its own task.
+pub async fn serve<H, F>(listener: TcpListener, handler: H)
+where
+    H: Fn(Request) -> F + Send + Sync + 'static,
+    F: Future<Output = Response> + Send,
+{
+    let handler = Arc::new(handler);
+    info!("HTTP - Listening on {:?}", listener.local_addr());
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, handler.as_ref()).await {
+                        warn!("HTTP - Dropped connection: {e}");
+                    }
+                });
+            }
+            Err(e) => {
+                error!("HTTP - Accept failed: {e}");
+                return;
+            }
+        }
+    }
+}
+
+async fn handle_connection<H, F>(stream: TcpStream, handler: &H) -> std::io::Result<()>
+where
+    H: Fn(Request) -> F,
+    F: Future<Output = Response>,
+{
+    let mut reader = BufReader::new(stream);
+    let request = timeout(READ_TIMEOUT, read_request(&mut reader)).await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "Request not read in time"))??;
+    let response = match request {
+        Some(request) => handler(request).await,
+        None => Response::status(400),
+    };
+
+    let mut stream = reader.into_inner();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status, reason(response.status), response.body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+// A line of the request head- None once the head is over its limit, or the connection closes mid-line.
+async fn read_head_line(reader: &mut BufReader<TcpStream>, remaining: &mut usize) -> std::io::Result<Option<String>> {
+    let mut line = String::new();
+    let read = (&mut *reader).take(*remaining as u64).read_line(&mut line).await?;
+    *remaining -= read;
+    Ok(line.ends_with('\n').then_some(line))
+}
+
+// None if the request is malformed, or too large.
+async fn read_request(reader: &mut BufReader<TcpStream>) -> std::io::Result<Option<Request>> {
+    let mut remaining = MAX_HEAD;
+    let Some(line) = read_head_line(reader, &mut remaining).await? else {
+        return Ok(None);
+    };
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+    let mut headers = HashMap::new();
+    loop {
+        let Some(line) = read_head_line(reader, &mut remaining).await? else {
+            return Ok(None);
+        };
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() >= MAX_HEADERS {
+            return Ok(None);
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length = headers.get("content-length").and_then(|l| l.parse::<usize>().ok()).unwrap_or(0);
+    if length > MAX_BODY {
+        return Ok(None);
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+
+    Ok(Some(Request { method: method.to_string(), path: path.to_string(), query, headers, body }))
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        422 => "Unprocessable Entity",
+        _ => "",
+    }
+}
+
+// Base64 HMAC-SHA256 of the message- as Shopify and Square sign webhooks.
+pub fn hmac_sha256_base64(key: &str, message: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).unwrap(); // HMAC takes keys of any length.
+    mac.update(message);
+    STANDARD.encode(mac.finalize().into_bytes())
+}
+
+// Constant-time check of a base64 HMAC-SHA256 signature.
+pub fn verify_hmac_sha256_base64(key: &str, message: &[u8], signature: &str) -> bool {
+    let Ok(signature) = STANDARD.decode(signature.trim()) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).unwrap();
+    mac.update(message);
+    mac.verify_slice(&signature).is_ok()
+}
@@ -1,20 +1,38 @@
-use std::collections::LinkedList;
+use std::collections::BTreeSet;
+use std::sync::Arc;
 use chrono::{DateTime, Utc};
 use log::{error, info, warn};
 use tokio::sync::mpsc::error::TryRecvError;
-use tokio::sync::mpsc::{Receiver};
+use tokio::sync::mpsc::{Receiver, UnboundedReceiver};
 use tokio::sync::watch::Sender;
 use crate::interpreter::history::History;
+use crate::interpreter::registry::PlatformRegistry;
 use crate::observations::Observation;
+use crate::real_world::scheduler::PlatformStatus;
 use crate::value::Value;
 
-pub async fn interpreter_worker(mut observations_in: Receiver<Observation<DateTime<Utc>>>, initial_value: Value, value_out: Sender<Option<Value>>) -> ! {
-    let mut history = History::new();
+pub async fn interpreter_worker(mut observations_in: Receiver<Observation<DateTime<Utc>>>, mut status_in: UnboundedReceiver<PlatformStatus>, initial_value: Value, registry: PlatformRegistry, value_out: Sender<Option<Value>>) -> ! {
+    let mut history = History::with_registry(Arc::new(registry));
+    let mut unreachable = BTreeSet::new(); // Platforms whose changes may arrive late, and that are not being written to.
 
     let mut stable_value = Some(initial_value);
     // Greedily capture all available observations.
     loop {
-        match observations_in.recv().await {
+        let observation = tokio::select! {
+            Some(status) = status_in.recv() => {
+                if status.reachable {
+                    info!("Interpreter - {} is reachable again", status.platform);
+                    unreachable.remove(&status.platform);
+                } else {
+                    warn!("Interpreter - {} is unreachable- its changes may arrive late!", status.platform);
+                    unreachable.insert(status.platform);
+                }
+                continue;
+            }
+            observation = observations_in.recv() => observation,
+        };
+
+        match observation {
             Some(observation) => {
                 info!("Interpreter - Got Observation: {observation:?}");
                 history.insert(observation, Utc::now());
@@ -22,9 +40,8 @@ pub async fn interpreter_worker(mut observations_in: Receiver<Observation<DateTi
                 loop {
                     match observations_in.try_recv() {
                         Ok(observation) => {
-                            let mut pruned = History {
-                                list: LinkedList::from_iter(history.insert(observation, Utc::now()).into_iter()),
-                            };
+                            let pruned_regions = history.insert(observation, Utc::now());
+                            let mut pruned = history.pruned(pruned_regions);
                             stable_value = match pruned.apply(stable_value, Utc::now()) {
                                 Ok(value) => Some(value),
                                 Err(_) => {
@@ -41,6 +58,9 @@ pub async fn interpreter_worker(mut observations_in: Receiver<Observation<DateTi
                 match history.apply(stable_value, Utc::now()) {
                     Ok(value) => {
                         info!("Calculated Result: {}, sending!", value);
+                        if !unreachable.is_empty() {
+                            warn!("Interpreter :: Result may be stale- unreachable: {:?}", unreachable);
+                        }
                         value_out.send(Some(value)).unwrap();
                     }, // Send to interfaces!
                     Err(conflict) => {
@@ -1,23 +1,48 @@
-use std::future::join;
-use chrono::{Utc};
-use log::info;
+use std::convert::Infallible;
+use std::future::{join, Future};
+use log::{error, info};
 use tokio::task::JoinSet;
-use tokio::sync::mpsc::{channel};
+use tokio::sync::mpsc::{channel, unbounded_channel};
 use tokio::sync::watch;
 use crate::real_world::config::{PlatformConfig, RealWorldConfig};
+use crate::real_world::credentials::Secrets;
+use crate::real_world::connector::{poll_worker, record_worker};
 use crate::real_world::interpreter::interpreter_worker;
-use crate::real_world::square::polling::{SquarePollingInterface};
-use crate::real_world::square::record::{SquareRecordInterface};
+use crate::real_world::reconciliation::{reconcile, startup};
+use crate::real_world::scheduler::Schedulers;
+use crate::real_world::square::calibration::CALIBRATION_SAMPLES;
+use crate::real_world::shopify::connector::ShopifyConnector;
+use crate::real_world::shopify::ShopifyMode;
+use crate::real_world::woocommerce::connector::WooCommerceConnector;
+use crate::real_world::woocommerce::WooCommerceMode;
 
 pub mod square;
 pub mod interpreter;
 pub mod config;
+pub mod connector;
+pub mod credentials;
+pub mod shopify;
+pub mod woocommerce;
+pub mod http;
+pub mod scheduler;
+pub mod reconciliation;
 
 
 pub async fn real_world_main(cfg: RealWorldConfig) {
     // Initialise interpreter channels
     let (interpreter_tx, mut interpreter_rx) = channel(10);
     let (value_tx, value_rx) = watch::channel(None);
+    let (status_tx, status_rx) = unbounded_channel();
+
+    // Every platform reports its count at startup- and waits for the stock reconciled from them.
+    let (counts_tx, counts_rx) = unbounded_channel();
+    let (reconciled_tx, reconciled_rx) = watch::channel(None);
+    let reconciliation = cfg.reconciliation();
+
+    // Credentials are resolved per platform, from here or the environment.
+    let secrets = cfg.secrets_file.as_ref().map(|path| Secrets::load(path)).unwrap_or_default();
+    // Requests are paced per account- telling the interpreter when one becomes unreachable.
+    let mut schedulers = Schedulers::new(cfg.scheduling.clone(), status_tx);
 
     // Start thread pools
     let mut polling_futures = JoinSet::new();
@@ -26,38 +51,91 @@ pub async fn real_world_main(cfg: RealWorldConfig) {
     // for each platform configured- initialise thread...
     for (name,platform_cfg) in cfg.platforms {
         info!("Discovered {}!", name);
+        let allocation = cfg.allocation.policy(&name);
+        let local_rx = value_rx.clone();
+        let local_tx = interpreter_tx.clone();
+        let (counts_out, reconciled) = (counts_tx.clone(), reconciled_rx.clone());
+
         match platform_cfg {
             PlatformConfig::Polling(square_cfg) => {
-                let mut new_interface = SquarePollingInterface::new(name, square_cfg);
-                new_interface.write(cfg.initial_value.clone()).await;
+                let mut connector = square_cfg.connector(name.clone(), &secrets, &mut schedulers).await;
 
-                let local_rx = value_rx.clone();
-                let local_tx = interpreter_tx.clone();
-                let local_initial_value = cfg.initial_value.clone();
-
-                polling_futures.spawn((async move || { // Move local copies into future.
-                    new_interface.poll_worker(local_rx, local_tx, (Utc::now(), 0)).await;
-                })());
+                polling_futures.spawn(supervise(name, async move { // Move local copies into future.
+                    let start = startup(&mut connector, allocation.clone(), counts_out, reconciled).await?;
+                    poll_worker(connector, square_cfg.interpretation, square_cfg.backoff, allocation, local_rx, local_tx, start.last_poll, start.offset).await
+                }));
             },
             PlatformConfig::Records(square_cfg) => {
-                let mut new_interface = SquareRecordInterface::new(name, square_cfg).await;
-                // TODO: Offset Worker.
-                new_interface.write(cfg.initial_value.clone()).await;
-                let local_rx = value_rx.clone();
-                let local_tx = interpreter_tx.clone();
-
-                record_futures.spawn((async move || { // Move local copies into future.
-                    new_interface.record_worker(local_rx, local_tx).await;
-                })());
+                let (mut connector, calibrator) = square_cfg.connector(name.clone(), &secrets, &mut schedulers).await;
+
+                record_futures.spawn(supervise(name, async move { // Move local copies into future.
+                    calibrator.calibrate(CALIBRATION_SAMPLES).await?; // Changes are never read uncalibrated.
+                    startup(&mut connector, allocation.clone(), counts_out, reconciled).await?;
+                    tokio::select! { // Recalibrates for as long as the connector runs.
+                        stopped = record_worker(connector, square_cfg.backoff, allocation, local_rx, local_tx) => stopped,
+                        stopped = calibrator.run() => stopped,
+                    }
+                }));
+            },
+            PlatformConfig::Shopify(shopify_cfg) => {
+                let backoff = shopify_cfg.backoff;
+                let mode = shopify_cfg.mode.clone();
+                let mut connector = ShopifyConnector::new(name.clone(), shopify_cfg, &secrets, &mut schedulers).await;
+
+                match mode {
+                    ShopifyMode::Polling(interpretation) => {
+                        polling_futures.spawn(supervise(name, async move { // Move local copies into future.
+                            let start = startup(&mut connector, allocation.clone(), counts_out, reconciled).await?;
+                            poll_worker(connector, interpretation, backoff, allocation, local_rx, local_tx, start.last_poll, start.offset).await
+                        }));
+                    },
+                    ShopifyMode::Webhooks { .. } => {
+                        record_futures.spawn(supervise(name, async move { // Move local copies into future.
+                            startup(&mut connector, allocation.clone(), counts_out, reconciled).await?;
+                            record_worker(connector, backoff, allocation, local_rx, local_tx).await
+                        }));
+                    },
+                }
+            },
+            PlatformConfig::WooCommerce(woo_cfg) => {
+                let backoff = woo_cfg.backoff;
+                let mode = woo_cfg.mode.clone();
+                let mut connector = WooCommerceConnector::new(name.clone(), woo_cfg, &secrets, &mut schedulers).await;
+
+                match mode {
+                    WooCommerceMode::Polling(interpretation) => {
+                        polling_futures.spawn(supervise(name, async move { // Move local copies into future.
+                            let start = startup(&mut connector, allocation.clone(), counts_out, reconciled).await?;
+                            poll_worker(connector, interpretation, backoff, allocation, local_rx, local_tx, start.last_poll, start.offset).await
+                        }));
+                    },
+                    WooCommerceMode::Orders { .. } => {
+                        record_futures.spawn(supervise(name, async move { // Move local copies into future.
+                            startup(&mut connector, allocation.clone(), counts_out, reconciled).await?;
+                            record_worker(connector, backoff, allocation, local_rx, local_tx).await
+                        }));
+                    },
+                }
             },
         }
     }
 
+    info!("Reconciling");
+    drop(counts_tx); // Only the platforms' copies remain- so reconciliation ends once each has reported.
+    let initial_value = reconcile(counts_rx, reconciliation, cfg.initial_value).await;
+    reconciled_tx.send(Some(initial_value)).unwrap();
+
     info!("Initialising Interpreter");
     // Initialise interpreter
-    let interpreter_future = interpreter_worker(interpreter_rx, cfg.initial_value, value_tx);
+    let interpreter_future = interpreter_worker(interpreter_rx, status_rx, initial_value, cfg.registry, value_tx);
 
     info!("Starting!");
     // Join all threads - run until termination.
-    join!(polling_futures.join_all(), interpreter_future).await;
-}
\ No newline at end of file
+    join!(polling_futures.join_all(), record_futures.join_all(), interpreter_future).await;
+}
+
+// Run a platform's worker- which stops only on a fatal failure, already reported to the interpreter.
+async fn supervise(name: String, worker: impl Future<Output = Result<Infallible, String>>) {
+    let Err(e) = worker.await;
+    error!("{name} - Stopped: {e}");
+}
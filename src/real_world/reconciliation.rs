@@ -0,0 +1,132 @@
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::io::{stdin, AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::sync::watch;
+use crate::allocation::{allocated, unallocated, ChannelAllocation};
+use crate::real_world::connector::PlatformConnector;
+use crate::value::Value;
+
+// Which stock to start from, when the platforms' counts disagree on it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Reconciliation {
+    MostRecent, // That of the platform whose count changed last.
+    Source(String), // That of the named platform- e.g. the POS.
+    Operator, // Asked for on stdin, after showing every count.
+    InitialValue, // The configured initial_value.
+}
+
+// A platform's count at startup.
+#[derive(Clone, Debug)]
+pub struct StartupCount {
+    pub(crate) platform: String,
+    pub(crate) count: Value, // As read- the channel's value, not stock.
+    pub(crate) stock: Value, // Stock the count was written for- the least, where several could be.
+    pub(crate) allocation: Option<ChannelAllocation>,
+    pub(crate) last_changed: Option<DateTime<Utc>>, // By the platform's clock, where it says.
+}
+
+// Where a platform's worker starts from, once the stock is reconciled.
+pub struct Startup {
+    pub(crate) last_poll: (DateTime<Utc>, Value), // Baseline of the first poll- so it observes only real changes.
+    pub(crate) offset: Value, // Stock minus the channel's value.
+}
+
+// Report the platform's count, and wait for the reconciled stock- writing it only where the count differs.
+// A platform failing fatally before it reports is left out of reconciliation.
+pub async fn startup<C: PlatformConnector>(
+    connector: &mut C,
+    allocation: Option<ChannelAllocation>,
+    counts_out: UnboundedSender<StartupCount>,
+    mut reconciled: watch::Receiver<Option<Value>>,
+) -> Result<Startup, String> {
+    let (count, sent, _) = connector.read_count().await?;
+    let last_changed = connector.last_changed().await?;
+    counts_out.send(StartupCount {
+        platform: connector.name().to_string(),
+        count,
+        stock: unallocated(allocation.as_ref(), count),
+        allocation: allocation.clone(),
+        last_changed,
+    }).unwrap();
+    drop(counts_out); // Reconciliation waits until every platform has reported.
+
+    let stock = reconciled.wait_for(Option::is_some).await.unwrap().unwrap();
+    let written = allocated(allocation.as_ref(), stock);
+    let last_poll = if written == count {
+        (sent, count)
+    } else {
+        info!("{} - Reconciling {} to {}", connector.name(), count, written);
+        connector.write(written).await?;
+        (Utc::now(), written)
+    };
+    Ok(Startup { last_poll, offset: stock - written })
+}
+
+// Agree on the stock to start from, from every platform's count- or apply the policy where they disagree.
+pub async fn reconcile(mut counts_in: UnboundedReceiver<StartupCount>, policy: Reconciliation, initial_value: Option<Value>) -> Value {
+    let mut counts = vec![];
+    while let Some(count) = counts_in.recv().await {
+        info!("Reconciliation - {} counts {} (stock {}), last changed {:?}", count.platform, count.count, count.stock, count.last_changed);
+        counts.push(count);
+    }
+
+    // A stock every count could be allocated from.
+    let agreed = counts.iter()
+        .map(|candidate| candidate.stock)
+        .find(|stock| counts.iter().all(|count| allocated(count.allocation.as_ref(), *stock) == count.count));
+    if let Some(stock) = agreed {
+        info!("Reconciliation - Platforms agree on {stock}");
+        return stock;
+    }
+
+    warn!("Reconciliation - Platforms disagree, applying {policy:?}");
+    let stock = match policy {
+        Reconciliation::MostRecent => match counts.iter().filter(|count| count.last_changed.is_some()).max_by_key(|count| count.last_changed) {
+            Some(count) => count.stock,
+            None => undecided("No platform says when its count last changed", &counts, initial_value).await,
+        },
+        Reconciliation::Source(platform) => match counts.iter().find(|count| count.platform == platform) {
+            Some(count) => count.stock,
+            None => undecided(&format!("{platform} did not report a count"), &counts, initial_value).await, // It failed fatally.
+        },
+        Reconciliation::Operator => ask_operator(&counts).await,
+        Reconciliation::InitialValue => match initial_value {
+            Some(stock) => stock,
+            None => undecided("No initial_value is configured", &counts, None).await,
+        },
+    };
+    info!("Reconciliation - Starting from {stock}");
+    stock
+}
+
+// Where the policy cannot pick a count- the initial value if one is configured, else the operator's.
+async fn undecided(reason: &str, counts: &[StartupCount], initial_value: Option<Value>) -> Value {
+    match initial_value {
+        Some(stock) => {
+            warn!("Reconciliation - {reason}, starting from the initial value");
+            stock
+        }
+        None => {
+            warn!("Reconciliation - {reason}, asking the operator");
+            ask_operator(counts).await
+        }
+    }
+}
+
+async fn ask_operator(counts: &[StartupCount]) -> Value {
+    println!("Platforms disagree on the stock:");
+    for count in counts {
+        println!("  {}: {} (stock {})", count.platform, count.count, count.stock);
+    }
+    let mut lines = BufReader::new(stdin()).lines();
+    loop {
+        println!("Stock to start from:");
+        let line = lines.next_line().await.unwrap().expect("stdin closed before a stock was given");
+        match line.trim().parse::<Value>() {
+            Ok(stock) => return stock,
+            Err(e) => println!("Not a stock ({e})"),
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use log::{error, info, warn};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::time::{sleep, sleep_until, Instant};
+use crate::validation::{issue, Validate, ValidationIssue};
+
+// How requests to each platform account are paced, retried and given up on.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    #[serde(default = "default_rate")]
+    pub(crate) rate: f64, // Requests per second, per account.
+    #[serde(default = "default_burst")]
+    pub(crate) burst: u32, // Requests that may be made at once, after a quiet period.
+    #[serde(default = "default_min_backoff")]
+    pub(crate) min_backoff: Duration, // Before the first retry- doubling, with jitter, after.
+    #[serde(default = "default_max_backoff")]
+    pub(crate) max_backoff: Duration,
+    #[serde(default = "default_failure_threshold")]
+    pub(crate) failure_threshold: u32, // Consecutive failures before the account is considered unreachable.
+    #[serde(default = "default_cooldown")]
+    pub(crate) cooldown: Duration, // Between attempts while unreachable.
+}
+
+fn default_rate() -> f64 { 5.0 }
+fn default_burst() -> u32 { 10 }
+fn default_min_backoff() -> Duration { Duration::from_millis(200) }
+fn default_max_backoff() -> Duration { Duration::from_secs(30) }
+fn default_failure_threshold() -> u32 { 5 }
+fn default_cooldown() -> Duration { Duration::from_secs(30) }
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        SchedulerConfig {
+            rate: default_rate(),
+            burst: default_burst(),
+            min_backoff: default_min_backoff(),
+            max_backoff: default_max_backoff(),
+            failure_threshold: default_failure_threshold(),
+            cooldown: default_cooldown(),
+        }
+    }
+}
+
+impl Validate for SchedulerConfig {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        if !(self.rate > 0.0) {
+            issue(issues, format!("{path}.rate"), "Rate must be greater than zero");
+        }
+        if self.burst == 0 {
+            issue(issues, format!("{path}.burst"), "Burst must be at least 1");
+        }
+        if self.min_backoff.is_zero() || self.min_backoff > self.max_backoff {
+            issue(issues, format!("{path}.min_backoff"), "Minimum backoff must be greater than zero, and at most the maximum");
+        }
+        if self.failure_threshold == 0 {
+            issue(issues, format!("{path}.failure_threshold"), "Failure threshold must be at least 1");
+        }
+    }
+}
+
+// Why a request failed- deciding whether, and when, it is retried.
+#[derive(Debug)]
+pub enum Failure {
+    Retryable(String), // e.g. timeouts, server errors.
+    RateLimited(Option<Duration>), // With the platform's Retry-After, if it gave one.
+    Fatal(String), // e.g. bad credentials- retrying cannot help.
+}
+
+// Sent to the interpreter as an account becomes unreachable, or reachable again.
+#[derive(Clone, Debug)]
+pub struct PlatformStatus {
+    pub(crate) platform: String,
+    pub(crate) reachable: bool,
+}
+
+struct Bucket {
+    tokens: f64, // Negative while paused for a rate limit.
+    refilled: Instant,
+}
+
+struct Breaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>, // Set while unreachable- requests wait until then, to try again.
+}
+
+// Paces every request to one account, shared by each platform using it.
+pub struct Scheduler {
+    account: String, // Never the credential itself.
+    config: SchedulerConfig,
+    bucket: Mutex<Bucket>,
+    breaker: Mutex<Breaker>,
+    platforms: Mutex<Vec<String>>, // Using this account- told of its reachability.
+    status_out: UnboundedSender<PlatformStatus>,
+}
+
+impl Scheduler {
+    // Make the request until it succeeds- or fails fatally, which the platform is reported unreachable for and its worker told.
+    pub async fn call<T, F, Fut>(&self, platform: &str, mut request: F) -> Result<T, String>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Failure>>,
+    {
+        let mut attempt = 0;
+        loop {
+            self.wait_until_closed().await;
+            self.acquire().await;
+            match request().await {
+                Ok(value) => {
+                    self.succeeded();
+                    return Ok(value);
+                }
+                Err(Failure::Fatal(message)) => {
+                    error!("{platform} - Fatal error: {message}");
+                    let _ = self.status_out.send(PlatformStatus { platform: platform.to_string(), reachable: false });
+                    return Err(message);
+                }
+                Err(Failure::RateLimited(retry_after)) => {
+                    let wait = retry_after.unwrap_or_else(|| self.backoff(attempt));
+                    warn!("{platform} - Rate limited, pausing {} for {wait:?}", self.account);
+                    self.pause(wait);
+                }
+                Err(Failure::Retryable(message)) => {
+                    let wait = self.backoff(attempt);
+                    error!("{platform} - {message}");
+                    warn!("{platform} - Waiting {wait:?} and trying again!");
+                    self.failed();
+                    sleep(wait).await;
+                }
+            }
+            attempt += 1;
+        }
+    }
+
+    // Wait for a token from the account's bucket.
+    async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let now = Instant::now();
+                let refill = now.duration_since(bucket.refilled).as_secs_f64() * self.config.rate;
+                bucket.tokens = (bucket.tokens + refill).min(self.config.burst as f64);
+                bucket.refilled = now;
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.config.rate)
+            };
+            sleep(wait).await;
+        }
+    }
+
+    // Hold every request to the account for this long.
+    fn pause(&self, wait: Duration) {
+        let mut bucket = self.bucket.lock().unwrap();
+        bucket.tokens = bucket.tokens.min(0.0) - wait.as_secs_f64() * self.config.rate;
+    }
+
+    // Exponential, with full jitter- so platforms sharing an account do not retry in step.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self.config.min_backoff.saturating_mul(1 << attempt.min(16)).min(self.config.max_backoff)
+            .max(self.config.min_backoff); // An unvalidated max below the min is not a range.
+        rand::rng().random_range(self.config.min_backoff..=ceiling)
+    }
+
+    async fn wait_until_closed(&self) {
+        let open_until = self.breaker.lock().unwrap().open_until;
+        if let Some(open_until) = open_until {
+            sleep_until(open_until).await;
+        }
+    }
+
+    fn failed(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.consecutive_failures += 1;
+        if breaker.consecutive_failures >= self.config.failure_threshold {
+            let opening = breaker.open_until.is_none();
+            breaker.open_until = Some(Instant::now() + self.config.cooldown);
+            drop(breaker);
+            if opening {
+                warn!("{} - Unreachable after {} failures, trying every {:?}", self.account, self.config.failure_threshold, self.config.cooldown);
+                self.report(false);
+            }
+        }
+    }
+
+    fn succeeded(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.consecutive_failures = 0;
+        if breaker.open_until.take().is_some() {
+            drop(breaker);
+            info!("{} - Reachable again", self.account);
+            self.report(true);
+        }
+    }
+
+    fn report(&self, reachable: bool) {
+        for platform in self.platforms.lock().unwrap().iter() {
+            let _ = self.status_out.send(PlatformStatus { platform: platform.clone(), reachable });
+        }
+    }
+}
+
+// One scheduler per account, shared by the platforms using it.
+pub struct Schedulers {
+    config: SchedulerConfig,
+    accounts: HashMap<String, Arc<Scheduler>>,
+    status_out: UnboundedSender<PlatformStatus>,
+}
+
+impl Schedulers {
+    pub fn new(config: SchedulerConfig, status_out: UnboundedSender<PlatformStatus>) -> Schedulers {
+        Schedulers { config, accounts: HashMap::new(), status_out }
+    }
+
+    // Scheduler of the account, registering the platform as a user of it.
+    pub fn account(&mut self, account: String, platform: &str) -> Arc<Scheduler> {
+        let scheduler = self.accounts.entry(account.clone()).or_insert_with(|| Arc::new(Scheduler {
+            account,
+            config: self.config.clone(),
+            bucket: Mutex::new(Bucket { tokens: self.config.burst as f64, refilled: Instant::now() }),
+            breaker: Mutex::new(Breaker { consecutive_failures: 0, open_until: None }),
+            platforms: Mutex::new(vec![]),
+            status_out: self.status_out.clone(),
+        }));
+        scheduler.platforms.lock().unwrap().push(platform.to_string());
+        scheduler.clone()
+    }
+}
+
+// Classify an HTTP reply- for platforms called through reqwest.
+pub fn classify(result: reqwest::Result<reqwest::Response>) -> Result<reqwest::Response, Failure> {
+    let response = result.map_err(|e| Failure::Retryable(format!("Request failed: {e}")))?;
+    let status = response.status();
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response.headers().get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<f64>().ok()) // Seconds- Shopify sends fractions.
+            .map(Duration::from_secs_f64);
+        Err(Failure::RateLimited(retry_after))
+    } else if status.is_server_error() || status == reqwest::StatusCode::REQUEST_TIMEOUT {
+        Err(Failure::Retryable(format!("{status} from {}", response.url())))
+    } else if status.is_client_error() {
+        Err(Failure::Fatal(format!("{status} from {}", response.url())))
+    } else {
+        Ok(response)
+    }
+}
@@ -0,0 +1,194 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, TimeDelta, Utc};
+use log::{error, info, warn};
+use reqwest::Client;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use crate::intervals::Interval;
+use crate::observations::Observation;
+use crate::ordering::PlatformMetadata;
+use crate::predicates::DefinitionPredicate;
+use crate::real_world::connector::{Change, PlatformConnector, RecentChanges, REDELIVERY_WINDOW_HOURS};
+use crate::real_world::credentials::{Secret, Secrets};
+use crate::real_world::scheduler::{classify, Failure, Scheduler, Schedulers};
+use crate::real_world::http::{serve, verify_hmac_sha256_base64, Request, Response};
+use crate::real_world::shopify::{InventoryLevel, InventoryLevelReply, InventoryLevels, SetRequest, ShopifyConfig, ShopifyMetadata, ShopifyMode, HMAC_HEADER, INVENTORY_TOPIC, TOKEN_HEADER, TOPIC_HEADER, WEBHOOK_ID_HEADER};
+use crate::value::Value;
+
+// A verified webhook, as received.
+struct Notification {
+    id: String,
+    level: InventoryLevel,
+    received: DateTime<Utc>,
+}
+
+// Shopify InventoryLevel of one item at one location.
+pub struct ShopifyConnector {
+    name: String,
+    client: Client,
+    config: ShopifyConfig,
+    token: Secret,
+    scheduler: Arc<Scheduler>, // Shared by platforms on the same shop.
+    notifications: Option<UnboundedReceiver<Notification>>, // In webhook mode.
+    written: Vec<(Value, String, DateTime<Utc>)>, // Our writes not yet seen as webhooks, with the updated_at Shopify gave them, and when we made them.
+}
+
+impl ShopifyConnector {
+    // Starts the webhook receiver, in webhook mode.
+    pub async fn new(name: String, config: ShopifyConfig, secrets: &Secrets, schedulers: &mut Schedulers) -> ShopifyConnector {
+        let token = config.token.resolve(secrets).unwrap_or_else(|e| panic!("{name} - Token: {e}"));
+        let scheduler = schedulers.account(format!("shopify:{}", token.fingerprint()), &name);
+        let notifications = match &config.mode {
+            ShopifyMode::Polling(_) => None,
+            ShopifyMode::Webhooks { listen, secret, .. } => {
+                let (notification_tx, notification_rx) = unbounded_channel();
+                let listener = TcpListener::bind(listen).await.expect(format!("{name} - Failed to listen on {listen}").as_str());
+                let secret = secret.resolve(secrets).unwrap_or_else(|e| panic!("{name} - Webhook secret: {e}"));
+                let (location_id, inventory_item_id) = (config.location_id, config.inventory_item_id);
+
+                tokio::spawn(serve(listener, move |request: Request| {
+                    let notification_tx = notification_tx.clone();
+                    let secret = secret.clone();
+                    async move {
+                        // Only signed inventory updates for our item are accepted.
+                        let signed = request.header(HMAC_HEADER)
+                            .is_some_and(|signature| verify_hmac_sha256_base64(secret.expose(), &request.body, signature));
+                        if request.method != "POST" || !signed {
+                            warn!("Shopify - Rejected unsigned webhook to {}", request.path);
+                            return Response::status(401);
+                        }
+                        if request.header(TOPIC_HEADER) != Some(INVENTORY_TOPIC) {
+                            return Response::status(200); // Acknowledge, so it is not retried.
+                        }
+                        let Ok(level) = serde_json::from_slice::<InventoryLevel>(&request.body) else {
+                            return Response::status(400);
+                        };
+                        if level.location_id == location_id && level.inventory_item_id == inventory_item_id {
+                            let id = request.header(WEBHOOK_ID_HEADER).unwrap_or(&level.updated_at).to_string();
+                            let _ = notification_tx.send(Notification { id, level, received: Utc::now() });
+                        }
+                        Response::status(200)
+                    }
+                }));
+                Some(notification_rx)
+            }
+        };
+
+        ShopifyConnector { name, client: Client::new(), config, token, scheduler, notifications, written: vec![] }
+    }
+
+    // Levels of the item at the location, with when the request was sent and replied to.
+    async fn levels(&self) -> Result<(InventoryLevels, DateTime<Utc>, DateTime<Utc>), String> {
+        let url = self.url("inventory_levels.json");
+        let query = [("inventory_item_ids", self.config.inventory_item_id), ("location_ids", self.config.location_id)];
+        let (client, token, url, query) = (&self.client, &self.token, &url, &query);
+        self.scheduler.call(&self.name, || async move {
+            let sent = Utc::now();
+            let response = classify(client.get(url).query(query).header(TOKEN_HEADER, token.expose()).send().await)?;
+            let levels = response.json::<InventoryLevels>().await.map_err(|e| Failure::Retryable(format!("Malformed reply: {e}")))?;
+            Ok((levels, sent, Utc::now()))
+        }).await
+    }
+
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}/admin/api/{}/{endpoint}", self.config.shop_url.trim_end_matches('/'), self.config.api_version)
+    }
+
+    // Our own write echoed back- not a change.
+    fn is_own_write(&mut self, level: &InventoryLevel) -> bool {
+        let echo = self.written.iter().position(|(value, updated_at, _)| Some(*value) == level.available && *updated_at == level.updated_at);
+        echo.map(|i| self.written.remove(i)).is_some()
+    }
+
+    fn parse_notification(&mut self, notification: Notification) -> Change {
+        if self.is_own_write(&notification.level) {
+            return Change { id: notification.id, at: notification.received, observation: None };
+        }
+        let (Some(available), Ok(updated_at)) = (notification.level.available, DateTime::parse_from_rfc3339(&notification.level.updated_at)) else {
+            error!("{} - Untracked or malformed level: {:?}", self.name, notification.level);
+            return Change { id: notification.id, at: notification.received, observation: None };
+        };
+        let updated_at = updated_at.with_timezone(&Utc);
+        let tolerance = match &self.config.mode {
+            ShopifyMode::Webhooks { clock_tolerance, .. } => *clock_tolerance,
+            ShopifyMode::Polling(_) => Duration::ZERO,
+        };
+
+        // Happened at updated_at on Shopify's clock- and certainly before we received it.
+        let earliest = (updated_at - tolerance).min(notification.received);
+        Change {
+            id: notification.id,
+            at: updated_at,
+            observation: Some(Observation {
+                interval: Interval(earliest, notification.received),
+                definition_predicate: DefinitionPredicate::LastAssn(available), // Webhooks carry the level, not the adjustment.
+                source: self.name.clone(),
+                platform_metadata: self.metadata(updated_at),
+            }),
+        }
+    }
+}
+
+impl PlatformConnector for ShopifyConnector {
+    type Cursor = RecentChanges; // Webhooks delivered within the redelivery window.
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn read_count(&mut self) -> Result<(Value, DateTime<Utc>, DateTime<Utc>), String> {
+        let (levels, sent, replied) = self.levels().await?;
+        let value = levels.inventory_levels.iter().filter_map(|level| level.available).sum::<Value>();
+        Ok((value, sent, replied))
+    }
+
+    async fn last_changed(&mut self) -> Result<Option<DateTime<Utc>>, String> {
+        let (levels, _, _) = self.levels().await?;
+        Ok(levels.inventory_levels.iter()
+            .filter_map(|level| DateTime::parse_from_rfc3339(&level.updated_at).ok())
+            .map(|updated_at| updated_at.with_timezone(&Utc))
+            .max())
+    }
+
+    async fn changes_since(&mut self, mut cursor: RecentChanges) -> Result<(Vec<Change>, RecentChanges), String> {
+        let mut received = vec![];
+        if let Some(notifications) = self.notifications.as_mut() {
+            while let Ok(notification) = notifications.try_recv() {
+                received.push(notification);
+            }
+        }
+        let changes = received.into_iter()
+            .map(|notification| self.parse_notification(notification))
+            .filter(|change| cursor.first_sight(change)) // Shopify may deliver a webhook more than once.
+            .collect();
+        // Echoes missed (or merged into a later update) by now never arrive.
+        let horizon = Utc::now() - TimeDelta::hours(REDELIVERY_WINDOW_HOURS);
+        cursor.forget_before(horizon);
+        self.written.retain(|(_, _, written_at)| *written_at >= horizon);
+        Ok((changes, cursor))
+    }
+
+    fn initial_cursor(&self) -> RecentChanges {
+        RecentChanges::default()
+    }
+
+    async fn write(&mut self, value: Value) -> Result<(), String> {
+        let url = self.url("inventory_levels/set.json");
+        let request = SetRequest { location_id: self.config.location_id, inventory_item_id: self.config.inventory_item_id, available: value };
+        let (client, token, url, request) = (&self.client, &self.token, &url, &request);
+        let reply = self.scheduler.call(&self.name, || async move {
+            let response = classify(client.post(url).header(TOKEN_HEADER, token.expose()).json(request).send().await)?;
+            response.json::<InventoryLevelReply>().await.map_err(|e| Failure::Retryable(format!("Malformed reply: {e}")))
+        }).await?;
+        info!("{} - Wrote Value: {:?}!", self.name, value);
+        if self.notifications.is_some() {
+            self.written.push((value, reply.inventory_level.updated_at, Utc::now()));
+        }
+        Ok(())
+    }
+
+    fn metadata(&self, timestamp: DateTime<Utc>) -> PlatformMetadata {
+        PlatformMetadata::Shopify(ShopifyMetadata { timestamp })
+    }
+}
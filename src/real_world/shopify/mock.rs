@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use chrono::Utc;
+use log::{error, info};
+use reqwest::Client;
+use tokio::net::TcpListener;
+use crate::real_world::http::{hmac_sha256_base64, serve, Request, Response};
+use crate::real_world::shopify::{AdjustRequest, InventoryLevel, InventoryLevelReply, InventoryLevels, SetRequest, HMAC_HEADER, INVENTORY_TOPIC, TOKEN_HEADER, TOPIC_HEADER, WEBHOOK_ID_HEADER};
+
+// In-memory stand-in for the Shopify InventoryLevel API- so the connector can be run without a store or network.
+// Levels start at 0 when first read. Any adjust or set (ours, or e.g. curl as a customer) sends a signed webhook, if configured.
+pub struct MockShopify {
+    token: String,
+    webhook: Option<(String, String)>, // URL and secret.
+    levels: Mutex<HashMap<(u64, u64), InventoryLevel>>, // By (location, item).
+    next_webhook_id: AtomicU64,
+    client: Client,
+}
+
+impl MockShopify {
+    pub fn new(token: String, webhook: Option<(String, String)>) -> MockShopify {
+        MockShopify { token, webhook, levels: Mutex::new(HashMap::new()), next_webhook_id: AtomicU64::new(0), client: Client::new() }
+    }
+
+    // Serve until the listener fails.
+    pub async fn run(self, listener: TcpListener) {
+        let mock = Arc::new(self);
+        serve(listener, move |request| {
+            let mock = mock.clone();
+            async move { mock.handle(request).await }
+        }).await
+    }
+
+    async fn handle(&self, request: Request) -> Response {
+        if request.header(TOKEN_HEADER) != Some(self.token.as_str()) {
+            return Response::json(401, r#"{"errors":"[API] Invalid API key or access token"}"#);
+        }
+
+        let endpoint = request.path.rsplit_once("/admin/api/")
+            .and_then(|(_, rest)| rest.split_once('/'))
+            .map(|(_, endpoint)| endpoint);
+        match (request.method.as_str(), endpoint) {
+            ("GET", Some("inventory_levels.json")) => {
+                let ids = |key: &str| request.query.get(key).map(|ids| ids.split(',').filter_map(|id| id.parse().ok()).collect()).unwrap_or(vec![]);
+                let (items, locations): (Vec<u64>, Vec<u64>) = (ids("inventory_item_ids"), ids("location_ids"));
+                let mut levels = self.levels.lock().unwrap();
+                let inventory_levels = locations.iter()
+                    .flat_map(|location| items.iter().map(move |item| (*location, *item)))
+                    .map(|key| levels.entry(key).or_insert_with(|| level(key, 0)).clone())
+                    .collect();
+                Response::json(200, serde_json::to_string(&InventoryLevels { inventory_levels }).unwrap())
+            }
+            ("POST", Some("inventory_levels/adjust.json")) => match serde_json::from_slice::<AdjustRequest>(&request.body) {
+                Ok(adjust) => self.update((adjust.location_id, adjust.inventory_item_id), |available| available + adjust.available_adjustment).await,
+                Err(e) => Response::json(422, format!(r#"{{"errors":"{e}"}}"#)),
+            },
+            ("POST", Some("inventory_levels/set.json")) => match serde_json::from_slice::<SetRequest>(&request.body) {
+                Ok(set) => self.update((set.location_id, set.inventory_item_id), |_| set.available).await,
+                Err(e) => Response::json(422, format!(r#"{{"errors":"{e}"}}"#)),
+            },
+            _ => Response::status(404),
+        }
+    }
+
+    async fn update(&self, key: (u64, u64), change: impl FnOnce(i64) -> i64) -> Response {
+        let updated = {
+            let mut levels = self.levels.lock().unwrap();
+            let current = levels.entry(key).or_insert_with(|| level(key, 0));
+            *current = level(key, change(current.available.unwrap_or(0)));
+            current.clone()
+        };
+        info!("MockShopify - Level now {:?}", updated);
+
+        if let Some((url, secret)) = &self.webhook {
+            let body = serde_json::to_string(&updated).unwrap();
+            let id = self.next_webhook_id.fetch_add(1, Ordering::Relaxed);
+            let sent = self.client.post(url)
+                .header(TOPIC_HEADER, INVENTORY_TOPIC)
+                .header(HMAC_HEADER, hmac_sha256_base64(secret, body.as_bytes()))
+                .header(WEBHOOK_ID_HEADER, format!("mock-{id}"))
+                .header("Content-Type", "application/json")
+                .body(body)
+                .send();
+            // Delivered after the reply, as Shopify does.
+            tokio::spawn(async move {
+                if let Err(e) = sent.await {
+                    error!("MockShopify - Webhook failed: {e}");
+                }
+            });
+        }
+        Response::json(200, serde_json::to_string(&InventoryLevelReply { inventory_level: updated }).unwrap())
+    }
+}
+
+fn level((location_id, inventory_item_id): (u64, u64), available: i64) -> InventoryLevel {
+    InventoryLevel { inventory_item_id, location_id, available: Some(available), updated_at: Utc::now().to_rfc3339() }
+}
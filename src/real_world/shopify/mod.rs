@@ -0,0 +1,106 @@
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::real_world::connector::PollingInterpretation;
+use crate::real_world::credentials::Credential;
+use crate::validation::{issue, Validate, ValidationIssue};
+
+pub mod connector;
+pub mod mock;
+#[cfg(test)]
+mod tests;
+
+pub const DEFAULT_API_VERSION: &str = "2024-01";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShopifyConfig {
+    pub(crate) shop_url: String, // e.g. https://my-shop.myshopify.com- or a mock server.
+    pub(crate) token: Credential, // Admin API access token.
+    #[serde(default = "default_api_version")]
+    pub(crate) api_version: String,
+    pub(crate) location_id: u64,
+    pub(crate) inventory_item_id: u64,
+    pub(crate) backoff: Duration, // Between polls, or between draining received webhooks.
+    pub(crate) mode: ShopifyMode,
+}
+
+fn default_api_version() -> String {
+    DEFAULT_API_VERSION.to_string()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ShopifyMode {
+    Polling(PollingInterpretation), // Poll the InventoryLevel, as for Square.
+    Webhooks {
+        listen: String, // Address to receive inventory_levels/update webhooks on, e.g. 0.0.0.0:8787
+        secret: Credential, // App secret, which signs webhooks.
+        #[serde(default = "default_clock_tolerance")]
+        clock_tolerance: Duration, // How far Shopify's updated_at may run ahead of our clock.
+    },
+}
+
+fn default_clock_tolerance() -> Duration {
+    Duration::from_secs(1)
+}
+
+#[derive(PartialEq, PartialOrd, Copy, Clone, Debug)]
+pub struct ShopifyMetadata {
+    pub(crate) timestamp: DateTime<Utc> // Poll sent time, or the level's updated_at.
+}
+
+impl Validate for ShopifyConfig {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        if !(self.shop_url.starts_with("https://") || self.shop_url.starts_with("http://")) {
+            issue(issues, format!("{path}.shop_url"), "Shop URL must start with https:// (or http:// for a mock)");
+        }
+        self.token.validate(&format!("{path}.token"), issues);
+        if self.backoff.is_zero() {
+            issue(issues, format!("{path}.backoff"), "Backoff must be greater than zero");
+        }
+        if let ShopifyMode::Webhooks { listen, secret, .. } = &self.mode {
+            if listen.parse::<std::net::SocketAddr>().is_err() {
+                issue(issues, format!("{path}.mode.Webhooks.listen"), format!("{listen} is not a socket address"));
+            }
+            secret.validate(&format!("{path}.mode.Webhooks.secret"), issues);
+        }
+    }
+}
+
+// Admin REST API payloads- shared by the connector and the mock.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InventoryLevel {
+    pub(crate) inventory_item_id: u64,
+    pub(crate) location_id: u64,
+    pub(crate) available: Option<i64>, // None when the item is not tracked.
+    pub(crate) updated_at: String, // RFC 3339- also the body of inventory_levels/update webhooks.
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InventoryLevels {
+    pub(crate) inventory_levels: Vec<InventoryLevel>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InventoryLevelReply {
+    pub(crate) inventory_level: InventoryLevel,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SetRequest {
+    pub(crate) location_id: u64,
+    pub(crate) inventory_item_id: u64,
+    pub(crate) available: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdjustRequest {
+    pub(crate) location_id: u64,
+    pub(crate) inventory_item_id: u64,
+    pub(crate) available_adjustment: i64,
+}
+
+pub const TOKEN_HEADER: &str = "X-Shopify-Access-Token";
+pub const TOPIC_HEADER: &str = "X-Shopify-Topic";
+pub const HMAC_HEADER: &str = "X-Shopify-Hmac-Sha256";
+pub const WEBHOOK_ID_HEADER: &str = "X-Shopify-Webhook-Id";
+pub const INVENTORY_TOPIC: &str = "inventory_levels/update";
@@ -0,0 +1,140 @@
+use std::time::Duration;
+use reqwest::Client;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::unbounded_channel;
+use tokio::time::sleep;
+use crate::predicates::DefinitionPredicate;
+use crate::real_world::connector::{Change, PlatformConnector, PollingInterpretation, RecentChanges};
+use crate::real_world::credentials::{Credential, Secrets};
+use crate::real_world::http::hmac_sha256_base64;
+use crate::real_world::scheduler::{SchedulerConfig, Schedulers};
+use crate::real_world::shopify::connector::ShopifyConnector;
+use crate::real_world::shopify::mock::MockShopify;
+use crate::real_world::shopify::{InventoryLevels, ShopifyConfig, ShopifyMode, HMAC_HEADER, INVENTORY_TOPIC, TOKEN_HEADER, TOPIC_HEADER, WEBHOOK_ID_HEADER};
+
+const TOKEN: &str = "shpat_test";
+const SECRET: &str = "webhook-secret";
+const LOCATION: u64 = 1;
+const ITEM: u64 = 2;
+
+// Mock on a free port, sending webhooks to the given address- its base URL.
+async fn mock(webhook_to: Option<&str>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let webhook = webhook_to.map(|address| (format!("http://{address}/"), SECRET.to_string()));
+    tokio::spawn(MockShopify::new(TOKEN.to_string(), webhook).run(listener));
+    url
+}
+
+fn free_address() -> String {
+    std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string()
+}
+
+async fn connector(shop_url: String, mode: ShopifyMode) -> ShopifyConnector {
+    let config = ShopifyConfig {
+        shop_url,
+        token: Credential::Inline(TOKEN.to_string()),
+        api_version: "2024-01".to_string(),
+        location_id: LOCATION,
+        inventory_item_id: ITEM,
+        backoff: Duration::from_millis(10),
+        mode,
+    };
+    let (status_tx, _) = unbounded_channel();
+    let mut schedulers = Schedulers::new(SchedulerConfig::default(), status_tx);
+    ShopifyConnector::new("Shopify".to_string(), config, &Secrets::default(), &mut schedulers).await
+}
+
+fn webhooks(listen: &str) -> ShopifyMode {
+    ShopifyMode::Webhooks { listen: listen.to_string(), secret: Credential::Inline(SECRET.to_string()), clock_tolerance: Duration::from_secs(1) }
+}
+
+// Adjust the level as a customer would- through the mock, not the connector.
+async fn adjust(shop_url: &str, by: i64) {
+    let body = serde_json::json!({ "location_id": LOCATION, "inventory_item_id": ITEM, "available_adjustment": by });
+    let response = Client::new().post(format!("{shop_url}/admin/api/2024-01/inventory_levels/adjust.json"))
+        .header(TOKEN_HEADER, TOKEN).json(&body).send().await.unwrap();
+    assert!(response.status().is_success());
+}
+
+// Changes received within a few seconds.
+async fn received(connector: &mut ShopifyConnector, cursor: &mut RecentChanges) -> Vec<Change> {
+    for _ in 0..50 {
+        let changes;
+        (changes, *cursor) = connector.changes_since(cursor.clone()).await.unwrap();
+        if !changes.is_empty() {
+            return changes;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    vec![]
+}
+
+#[tokio::test]
+async fn reads_available() {
+    let shop_url = mock(None).await;
+    adjust(&shop_url, 12).await;
+    let mut connector = connector(shop_url, ShopifyMode::Polling(PollingInterpretation::Assignment)).await;
+
+    let (value, sent, replied) = connector.read_count().await.unwrap();
+    assert_eq!(value, 12);
+    assert!(sent <= replied);
+}
+
+#[tokio::test]
+async fn writes_through_set() {
+    let shop_url = mock(None).await;
+    let mut connector = connector(shop_url.clone(), ShopifyMode::Polling(PollingInterpretation::Assignment)).await;
+    connector.write(7).await.unwrap();
+
+    let levels: InventoryLevels = Client::new().get(format!("{shop_url}/admin/api/2024-01/inventory_levels.json"))
+        .query(&[("inventory_item_ids", ITEM), ("location_ids", LOCATION)])
+        .header(TOKEN_HEADER, TOKEN).send().await.unwrap().json().await.unwrap();
+    assert_eq!(levels.inventory_levels[0].available, Some(7));
+}
+
+#[tokio::test]
+async fn observes_signed_webhooks() {
+    let listen = free_address();
+    let shop_url = mock(Some(&listen)).await;
+    let mut connector = connector(shop_url.clone(), webhooks(&listen)).await;
+    let mut cursor = connector.initial_cursor();
+
+    adjust(&shop_url, -3).await;
+    let changes = received(&mut connector, &mut cursor).await;
+    assert_eq!(changes.len(), 1);
+    let observation = changes[0].observation.as_ref().expect("A customer's adjustment is observed");
+    assert!(matches!(observation.definition_predicate, DefinitionPredicate::LastAssn(-3)));
+}
+
+#[tokio::test]
+async fn rejects_unsigned_webhooks() {
+    let listen = free_address();
+    let mut connector = connector(mock(None).await, webhooks(&listen)).await;
+    let mut cursor = connector.initial_cursor();
+
+    let body = serde_json::json!({ "inventory_item_id": ITEM, "location_id": LOCATION, "available": 1, "updated_at": "2024-01-01T00:00:00Z" }).to_string();
+    let send = |signature: String| Client::new().post(format!("http://{listen}/"))
+        .header(TOPIC_HEADER, INVENTORY_TOPIC)
+        .header(WEBHOOK_ID_HEADER, "forged")
+        .header(HMAC_HEADER, signature)
+        .body(body.clone())
+        .send();
+    assert_eq!(send(hmac_sha256_base64("wrong-secret", body.as_bytes())).await.unwrap().status(), 401);
+    assert_eq!(send("not base64".to_string()).await.unwrap().status(), 401);
+
+    assert!(received(&mut connector, &mut cursor).await.is_empty());
+}
+
+#[tokio::test]
+async fn skips_own_write_echo() {
+    let listen = free_address();
+    let shop_url = mock(Some(&listen)).await;
+    let mut connector = connector(shop_url, webhooks(&listen)).await;
+    let mut cursor = connector.initial_cursor();
+
+    connector.write(20).await.unwrap();
+    let changes = received(&mut connector, &mut cursor).await;
+    assert_eq!(changes.len(), 1, "The echo is received");
+    assert!(changes[0].observation.is_none(), "But not observed");
+}
@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::{DateTime, TimeDelta, Utc};
+use log::{info, warn};
+use squareup::api::InventoryApi;
+use squareup::models::enums::{InventoryChangeType, InventoryState};
+use squareup::models::{BatchChangeInventoryRequest, InventoryAdjustment, InventoryChange};
+use tokio::time::sleep;
+use uuid::Uuid;
+use crate::real_world::credentials::Secret;
+use crate::real_world::scheduler::Scheduler;
+use crate::real_world::square::connector::{classify, inventory_api};
+use crate::real_world::square::{SquareClientConfig, Target};
+
+pub const CALIBRATION_SAMPLES: usize = 5; // Taken at startup, before any changes are read.
+const DEVIATION_WINDOW: usize = 20; // Samples the bounds are drawn from- older ones are forgotten, so drift is followed.
+
+// Bounds of Square's clock minus ours- zero until calibrated.
+#[derive(Debug, Default)]
+pub struct Deviation {
+    samples: VecDeque<(TimeDelta, TimeDelta)>, // (Min, Max) of each sample, oldest first.
+    bounds: (TimeDelta, TimeDelta),
+}
+
+impl Deviation {
+    pub fn bounds(&self) -> (TimeDelta, TimeDelta) {
+        self.bounds
+    }
+
+    // Every sample bounds the same deviation- so the bounds tighten to where they all agree.
+    // Where they do not (Square's clock stepped, or drifted within the window), widen to cover them all until they do again.
+    fn add(&mut self, name: &str, sample: (TimeDelta, TimeDelta)) {
+        self.samples.push_back(sample);
+        if self.samples.len() > DEVIATION_WINDOW {
+            self.samples.pop_front();
+        }
+
+        let agreed_min = self.samples.iter().map(|(min, _)| *min).max().unwrap();
+        let agreed_max = self.samples.iter().map(|(_, max)| *max).min().unwrap();
+        let bounds = if agreed_min <= agreed_max {
+            (agreed_min, agreed_max)
+        } else {
+            warn!("{name} - Deviation samples disagree ({agreed_min} > {agreed_max}), widening bounds");
+            (self.samples.iter().map(|(min, _)| *min).min().unwrap(), self.samples.iter().map(|(_, max)| *max).max().unwrap())
+        };
+
+        if bounds != self.bounds {
+            info!("{name} - Deviation bounds {}, {} -> {}, {}", self.bounds.0, self.bounds.1, bounds.0, bounds.1);
+            self.bounds = bounds;
+        }
+    }
+}
+
+// Bounds Square's clock deviation, by adjusting a separate calibration item and timing the changes it records.
+pub struct Calibrator {
+    name: String,
+    inventory_api: InventoryApi, // Its own client- so calibrating does not hold up the connector.
+    calibration_target: Target,
+    scheduler: Arc<Scheduler>, // Shared with the connector- calibration writes count against the account's rate.
+    deviation: Arc<Mutex<Deviation>>, // Read by the connector as it interprets changes.
+    interval: Duration, // Between recalibrations.
+}
+
+impl Calibrator {
+    pub fn new(name: String, token: &Secret, calibration_target: Target, client: &SquareClientConfig, scheduler: Arc<Scheduler>, deviation: Arc<Mutex<Deviation>>, interval: Duration) -> Calibrator {
+        Calibrator { inventory_api: inventory_api(token, client), name, calibration_target, scheduler, deviation, interval }
+    }
+
+    // Take several samples at once- before the connector reads any changes. Failing fatally, rather than reading them uncalibrated.
+    pub async fn calibrate(&self, samples: usize) -> Result<(), String> {
+        for i in 0..samples {
+            info!("{} - Deviation Query, request {}/{}", self.name, i, samples);
+            let sample = self.sample().await.map_err(|e| format!("Calibration failed: {e}"))?;
+            self.deviation.lock().unwrap().add(&self.name, sample);
+            sleep(Duration::from_millis(400)).await; // Backoff to avoid rate limit spoiling calculations.
+        }
+        let (min, max) = self.deviation.lock().unwrap().bounds();
+        info!("{} Calibrated: {}, {}", self.name, min, max);
+        Ok(())
+    }
+
+    // Keep sampling, for as long as the connector runs- Square's clock drifts from ours. Until a request fails fatally.
+    pub async fn run(&self) -> Result<Infallible, String> {
+        loop {
+            sleep(self.interval).await;
+            let sample = self.sample().await.map_err(|e| format!("Recalibration failed: {e}"))?;
+            self.deviation.lock().unwrap().add(&self.name, sample);
+        }
+    }
+
+    // Square's clock minus ours lies between these- its record of the adjustment was made between our sending and receiving it.
+    async fn sample(&self) -> Result<(TimeDelta, TimeDelta), String> {
+        let request_key = Uuid::new_v4().to_string();
+        let request = BatchChangeInventoryRequest {
+            idempotency_key: request_key.clone(),
+            changes: Some(vec![
+                InventoryChange {
+                    r#type: Some(InventoryChangeType::Adjustment),
+                    physical_count: None,
+                    adjustment: Some(InventoryAdjustment {
+                        id: None,
+                        reference_id: Some(request_key.clone()),
+                        from_state: Some(InventoryState::None),
+                        to_state: Some(InventoryState::InStock),
+                        location_id: Some(self.calibration_target.0.clone()),
+                        catalog_object_id: Some(self.calibration_target.1.clone()),
+                        catalog_object_type: None,
+                        quantity: Some("1".to_string()),
+                        total_price_money: None,
+                        occurred_at: Some(Default::default()),
+                        created_at: None,
+                        source: None,
+                        employee_id: None,
+                        team_member_id: None,
+                        transaction_id: None,
+                        refund_id: None,
+                        purchase_order_id: None,
+                        goods_receipt_id: None,
+                        adjustment_group: None,
+                    }),
+                    transfer: None,
+                    measurement_unit: None,
+                    measurement_unit_id: None,
+                }
+            ]),
+            ignore_unchanged_counts: None,
+        };
+        let (inventory_api, request) = (&self.inventory_api, &request);
+        let (resp, sent, replied) = self.scheduler.call(&self.name, || async move {
+            let sent: DateTime<Utc> = Utc::now();
+            let resp = inventory_api.batch_change_inventory(request).await.map_err(classify)?;
+            Ok((resp, sent, Utc::now()))
+        }).await?;
+        let change = resp.changes.as_ref().expect(format!("Failed to do Calibration: {}", self.name.clone()).as_str()).iter().nth(0).unwrap();
+        let created_at = change.adjustment.as_ref().unwrap().created_at.as_ref().unwrap();
+        let timestamp_dev: DateTime<Utc> = DateTime::from(created_at.clone());
+        let timestamp_min = timestamp_dev;
+        let timestamp_max = timestamp_dev + TimeDelta::milliseconds(1);
+
+        Ok((timestamp_min - replied, timestamp_max - sent))
+    }
+}
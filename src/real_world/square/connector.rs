@@ -0,0 +1,378 @@
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::str::FromStr;
+use std::time::Duration;
+use chrono::{DateTime, TimeDelta, Utc};
+use log::{error, info, warn};
+use squareup::api::InventoryApi;
+use squareup::config::{BaseUri, Configuration, Environment};
+use squareup::http::client::{HttpClientConfiguration, RetryConfiguration};
+use squareup::http::Headers;
+use squareup::models::enums::InventoryState::InStock;
+use squareup::models::enums::{ErrorCategory, InventoryChangeType};
+use squareup::models::errors::SquareApiError;
+use squareup::models::{BatchChangeInventoryRequest, BatchRetrieveInventoryChangesRequest, DateTime as SquareDateTime, InventoryChange, InventoryCount, InventoryPhysicalCount, RetrieveInventoryCountParams};
+use serde::{Deserialize, Serialize};
+use squareup::SquareClient;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::time::{sleep, timeout, Instant};
+use uuid::Uuid;
+use crate::intervals::Interval;
+use crate::observations::Observation;
+use crate::ordering::PlatformMetadata;
+use crate::predicates::DefinitionPredicate;
+use crate::real_world::connector::{Change, PlatformConnector, RecentChanges};
+use crate::real_world::credentials::{Secret, Secrets};
+use crate::real_world::scheduler::{Failure, Scheduler};
+use crate::real_world::square::calibration::Deviation;
+use crate::real_world::square::webhook::{receive, SquareWebhookConfig};
+use crate::real_world::square::{SquareClientConfig, SquareEnvironment, SquareMetadata, Target, IGNORE};
+use crate::value::Value;
+
+const CHANGE_OVERLAP_MS: i64 = 5000; // Changes are fetched from this far before the watermark, as Square may record them late.
+
+// Where to fetch changes from- persisted, if configured, so a restart resumes rather than skipping or repeating changes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SquareCursor {
+    watermark: DateTime<Utc>, // When we last fetched, by Square's clock at the earliest- changes updated after it are yet to be fetched.
+    recent: RecentChanges, // Changes by when they were last fetched- those within the overlap of the watermark can be fetched again.
+}
+
+// Square inventory of one item at one location.
+pub struct SquareConnector {
+    pub(crate) name: String,
+    pub(crate) inventory_api: InventoryApi,
+    pub(crate) target: Target,
+    deviation: Arc<Mutex<Deviation>>, // Kept up to date by the calibrator, if any.
+    webhooks: Option<Arc<AsyncMutex<Webhooks>>>, // When subscribed to count updates- shared with the wait for them.
+    state_path: Option<PathBuf>, // Where the cursor is persisted.
+    scheduler: Arc<Scheduler>, // Shared by platforms on the same account.
+}
+
+struct Webhooks {
+    wakeups: Option<UnboundedReceiver<()>>, // None once the receiver stops.
+    fallback: Duration,
+    last_read: Instant, // When we last woke to read- the fallback is measured from here.
+}
+
+impl SquareConnector {
+    pub fn new(name: String, token: &Secret, target: Target, client: &SquareClientConfig, scheduler: Arc<Scheduler>) -> SquareConnector {
+        info!("{name} - Using Square {:?}", client.environment);
+        let inventory_api = inventory_api(token, client);
+        SquareConnector { name, inventory_api, target, deviation: Arc::default(), webhooks: None, state_path: None, scheduler }
+    }
+
+    // Shared with a calibrator- which bounds it as Square's clock drifts.
+    pub fn deviation(&self) -> Arc<Mutex<Deviation>> {
+        self.deviation.clone()
+    }
+
+    // Read as soon as Square notifies us of a count update, rather than every backoff.
+    pub async fn listen(&mut self, config: &SquareWebhookConfig, secrets: &Secrets) {
+        let signature_key = config.signature_key.resolve(secrets).unwrap_or_else(|e| panic!("{} - Webhook signature key: {e}", self.name));
+        let wakeups = receive(&self.name, config, signature_key, self.target.clone()).await;
+        self.webhooks = Some(Arc::new(AsyncMutex::new(Webhooks { wakeups: Some(wakeups), fallback: config.fallback, last_read: Instant::now() })));
+    }
+
+    // Resume fetching changes from the cursor saved here, saving it after every fetch.
+    pub fn persist_to(&mut self, state_path: PathBuf) {
+        self.state_path = Some(state_path);
+    }
+
+    fn save(&self, cursor: &SquareCursor) {
+        let Some(state_path) = &self.state_path else {
+            return;
+        };
+        // Replace atomically- a torn write would lose the watermark.
+        let temporary = state_path.with_extension("tmp");
+        let saved = fs::write(&temporary, serde_json::to_string(cursor).unwrap())
+            .and_then(|_| fs::rename(&temporary, state_path));
+        if let Err(e) = saved {
+            error!("{} - Failed to save cursor to {}: {e}", self.name, state_path.display());
+        }
+    }
+
+    pub async fn request(&self) -> Result<(Value, DateTime<Utc>, DateTime<Utc>), String> {
+        let (counts, sent, replied) = self.counts().await?;
+        let value = counts.iter()
+            .filter(|c| c.state == InStock)
+            .map(|c| Value::from_str(&c.quantity).unwrap())
+            .sum::<Value>();
+
+        Ok((value, sent, replied))
+    }
+
+    // Counts of the target in each state, with when the request was sent and replied to.
+    async fn counts(&self) -> Result<(Vec<InventoryCount>, DateTime<Utc>, DateTime<Utc>), String> {
+        let params = RetrieveInventoryCountParams {
+            location_ids: Some(vec![self.target.0.clone()]),
+            cursor: None
+        };
+
+        let (inventory_api, target, params) = (&self.inventory_api, &self.target, &params);
+        let (response, sent, replied) = self.scheduler.call(&self.name, || async move {
+            let sent = Utc::now();
+            let response = inventory_api.retrieve_inventory_count(target.1.clone(), params.clone()).await.map_err(classify)?;
+            Ok((response, sent, Utc::now()))
+        }).await?;
+        Ok((response.counts.unwrap(), sent, replied))
+    }
+
+    // Every change updated after since- following cursors across pages.
+    pub async fn request_events(&self, since: DateTime<Utc>) -> Result<Vec<InventoryChange>, String> {
+        let mut request = BatchRetrieveInventoryChangesRequest {
+            catalog_object_ids: Some(vec![self.target.1.clone()]),
+            location_ids: Some(vec![self.target.0.clone()]),
+            updated_after: Some((&since).into()),
+            ..Default::default()
+        };
+
+        let mut changes = vec![];
+        loop {
+            let response = self.scheduler.call(&self.name, || {
+                let mut request = request.clone();
+                async move { self.inventory_api.batch_retrieve_inventory_changes(&mut request).await.map_err(classify) }
+            }).await?;
+            changes.extend(response.changes.unwrap_or_else(|| vec![]));
+            match response.cursor {
+                Some(cursor) => request.cursor = Some(cursor), // Next page.
+                None => return Ok(changes),
+            }
+        }
+    }
+
+    pub fn parse_change(&self, change: InventoryChange) -> Change {
+        match change.r#type.as_ref().unwrap() {
+            InventoryChangeType::PhysicalCount => {
+                // Why did they structure it like this :sob:
+                let physical_count = change.physical_count.unwrap();
+
+                if physical_count.reference_id.is_some_and(|id| id.eq(IGNORE)) {
+                    let at = physical_count.created_at.unwrap().into();
+                    return Change { id: physical_count.id.unwrap(), at, observation: None }; // Ignore event.
+                }
+                let created_at = physical_count.created_at.unwrap();
+                let (deviation_min, deviation_max) = self.deviation.lock().unwrap().bounds();
+                let min = DateTime::<Utc>::from(created_at.clone()) - deviation_max;
+                let max = DateTime::<Utc>::from(created_at.clone()) - deviation_min;
+
+                Change { id: physical_count.id.unwrap(), at: created_at.clone().into(), observation: Some(Observation::<DateTime<Utc>> {
+                    interval: Interval(min, max),
+                    definition_predicate: DefinitionPredicate::LastAssn(Value::from_str(&physical_count.quantity.unwrap()).unwrap()),
+                    source: self.name.clone(),
+                    platform_metadata: self.metadata(created_at.into()),
+                }) }
+            },
+            InventoryChangeType::Adjustment => {
+                let adjustment = change.adjustment.unwrap();
+
+                if adjustment.reference_id.is_some_and(|id| id.eq(IGNORE)) {
+                    let at = adjustment.created_at.unwrap().into();
+                    return Change { id: adjustment.id.unwrap(), at, observation: None }; // Ignore event.
+                }
+
+                let created_at = adjustment.created_at.unwrap();
+                let (deviation_min, deviation_max) = self.deviation.lock().unwrap().bounds();
+                let min = DateTime::<Utc>::from(created_at.clone()) - deviation_max;
+                let max = DateTime::<Utc>::from(created_at.clone()) - deviation_min;
+                let mut quantity = Value::from_str(&adjustment.quantity.unwrap()).unwrap();
+
+                // Came FROM instock- must be a decrement.
+                if matches!(adjustment.from_state, Some(InStock)) {
+                    quantity = -quantity;
+                }
+
+                Change { id: adjustment.id.unwrap(), at: created_at.clone().into(), observation: Some(Observation::<DateTime<Utc>> {
+                    interval: Interval(min, max),
+                    definition_predicate: DefinitionPredicate::AllMut(quantity),
+                    source: self.name.clone(),
+                    platform_metadata: self.metadata(created_at.into()),
+                }) }
+            },
+            _ => {
+                // We do not test with transfers
+                error!("We do not test across multiple locations!");
+                panic!()
+            }
+        }
+    }
+
+    pub async fn write_physical_count(&mut self, value: Value) -> Result<(), String> {
+        let params = BatchChangeInventoryRequest {
+            idempotency_key: Uuid::new_v4().to_string(),
+            changes: Some(vec![
+                InventoryChange {
+                    r#type: Some(InventoryChangeType::PhysicalCount),
+                    physical_count: Some(
+                        InventoryPhysicalCount {
+                            id: None,
+                            reference_id: Some(IGNORE.to_string()),
+                            catalog_object_id: Some(self.target.1.clone()),
+                            catalog_object_type: None,
+                            state: Some(InStock),
+                            location_id: Some(self.target.0.clone()),
+                            quantity: Some(value.to_string()),
+                            source: None,
+                            employee_id: None,
+                            team_member_id: None,
+                            occurred_at: Some(SquareDateTime::now()),
+                            created_at: None,
+                        }
+                    ),
+                    adjustment: None,
+                    transfer: None,
+                    measurement_unit: None,
+                    measurement_unit_id: None,
+                }
+            ]),
+            ..Default::default()
+        };
+
+        let (inventory_api, params) = (&self.inventory_api, &params);
+        self.scheduler.call(&self.name, || async move {
+            inventory_api.batch_change_inventory(params).await.map_err(classify)
+        }).await?;
+        info!("{} - Wrote Value: {:?}!", self.name, value);
+        Ok(())
+    }
+}
+
+// Inventory API of the account the token belongs to, on the configured Square.
+pub(crate) fn inventory_api(token: &Secret, client: &SquareClientConfig) -> InventoryApi {
+    // Auth Token per client- so platforms on different accounts do not share one.
+    let mut default_headers = Headers::default();
+    default_headers.insert("Authorization", &format!("Bearer {}", token.expose()));
+
+    let (environment, base_uri) = match &client.environment {
+        SquareEnvironment::Sandbox => (Environment::Sandbox, BaseUri::default()),
+        SquareEnvironment::Production => (Environment::Production, BaseUri::default()),
+        SquareEnvironment::Custom(url) => (Environment::Sandbox, BaseUri::Custom(url.clone())), // The base URI overrides the environment's.
+    };
+
+    InventoryApi::new(SquareClient::try_new(Configuration {
+        environment,
+        http_client_config: HttpClientConfiguration {
+            timeout: client.timeout.as_secs() as u32,
+            default_headers,
+            retry_configuration: RetryConfiguration {
+                retries_count: client.retries,
+                wait_time: client.retry_wait,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        base_uri,
+    }).unwrap())
+}
+
+// By the category Square gives- failures without one (e.g. timeouts) are retried.
+pub(crate) fn classify(e: SquareApiError) -> Failure {
+    let categories: Vec<ErrorCategory> = e.errors.iter().flatten().map(|error| error.category.clone()).collect();
+    if categories.contains(&ErrorCategory::RateLimitError) {
+        Failure::RateLimited(None) // Square gives no Retry-After.
+    } else if categories.iter().any(|category| matches!(category, ErrorCategory::AuthenticationError | ErrorCategory::InvalidRequestError)) {
+        Failure::Fatal(format!("{e:?}"))
+    } else {
+        Failure::Retryable(format!("API Error: {e:?}"))
+    }
+}
+
+impl PlatformConnector for SquareConnector {
+    type Cursor = SquareCursor;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn read_count(&mut self) -> Result<(Value, DateTime<Utc>, DateTime<Utc>), String> {
+        self.request().await
+    }
+
+    async fn last_changed(&mut self) -> Result<Option<DateTime<Utc>>, String> {
+        let (counts, _, _) = self.counts().await?;
+        Ok(counts.iter()
+            .filter(|c| c.state == InStock)
+            .map(|c| DateTime::<Utc>::from(c.calculated_at.clone()))
+            .max())
+    }
+
+    // Fetched by updated_after- which changes do not report. So the watermark, and when each change was fetched, are taken by
+    // Square's clock from ours: a change fetched again was updated after the watermark, and before we last fetched it.
+    async fn changes_since(&mut self, mut cursor: SquareCursor) -> Result<(Vec<Change>, SquareCursor), String> {
+        let overlap = TimeDelta::milliseconds(CHANGE_OVERLAP_MS);
+        let (deviation_min, _) = self.deviation.lock().unwrap().bounds();
+        let sent = Utc::now() + deviation_min;
+        let fetched = self.request_events(cursor.watermark - overlap).await?;
+        let (_, deviation_max) = self.deviation.lock().unwrap().bounds();
+        let replied = Utc::now() + deviation_max;
+
+        let mut changes: Vec<Change> = fetched.into_iter()
+            .map(|change| self.parse_change(change))
+            .filter(|change| cursor.recent.first_sight_at(change, replied))
+            .collect();
+        changes.sort_by_key(|change| change.at);
+
+        cursor.watermark = cursor.watermark.max(sent);
+        cursor.recent.forget_before(cursor.watermark - overlap);
+        if !changes.is_empty() {
+            self.save(&cursor);
+        }
+        Ok((changes, cursor))
+    }
+
+    // The saved cursor, or now by Square's clock when there is none.
+    fn initial_cursor(&self) -> SquareCursor {
+        let saved = self.state_path.as_ref()
+            .and_then(|state_path| fs::read_to_string(state_path).ok())
+            .and_then(|contents| serde_json::from_str::<SquareCursor>(&contents).ok());
+        match saved {
+            Some(cursor) => {
+                info!("{} - Resuming changes from {}", self.name, cursor.watermark);
+                cursor
+            }
+            None => {
+                let (deviation_min, _) = self.deviation.lock().unwrap().bounds(); // Calibrated before any changes are read.
+                SquareCursor { watermark: Utc::now() + deviation_min, recent: RecentChanges::default() }
+            }
+        }
+    }
+
+    async fn write(&mut self, value: Value) -> Result<(), String> {
+        self.write_physical_count(value).await
+    }
+
+    fn metadata(&self, timestamp: DateTime<Utc>) -> PlatformMetadata {
+        PlatformMetadata::Square(SquareMetadata { timestamp })
+    }
+
+    // With webhooks, the backoff only spaces reads out- we then wait for a notification, or the fallback.
+    fn wait_for_changes(&mut self, backoff: Duration) -> impl Future<Output = ()> + Send + 'static {
+        let (name, webhooks) = (self.name.clone(), self.webhooks.clone());
+        async move {
+            sleep(backoff).await;
+            let Some(webhooks) = webhooks else {
+                return;
+            };
+            let mut webhooks = webhooks.lock().await;
+            let remaining = webhooks.fallback.saturating_sub(webhooks.last_read.elapsed());
+            let fallback = webhooks.fallback;
+            let Some(wakeups) = webhooks.wakeups.as_mut() else {
+                return; // Polling instead.
+            };
+
+            match timeout(remaining, wakeups.recv()).await {
+                Ok(Some(())) => while wakeups.try_recv().is_ok() {}, // One read covers every pending notification.
+                Ok(None) => {
+                    error!("{name} - Webhook receiver stopped, falling back to polling!");
+                    webhooks.wakeups = None;
+                    return;
+                }
+                Err(_) => warn!("{name} - No webhook for {fallback:?}, reading anyway"),
+            }
+            webhooks.last_read = Instant::now();
+        }
+    }
+}
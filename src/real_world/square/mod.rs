@@ -1,11 +1,76 @@
+use std::time::Duration;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::validation::{issue, Validate, ValidationIssue};
 
 pub mod polling;
 pub mod record;
+pub mod connector;
+pub mod webhook;
+pub mod calibration;
 
 pub const IGNORE: &str = "IGNORE";
-pub type Target = (String, String);
+pub type Target = (String, String); // (Location ID, Catalog Object ID)
 
+pub(crate) fn validate_target(target: &Target, path: &str, issues: &mut Vec<ValidationIssue>) {
+    if target.0.trim().is_empty() {
+        issue(issues, format!("{path}[0]"), "Location ID must not be empty");
+    }
+    if target.1.trim().is_empty() {
+        issue(issues, format!("{path}[1]"), "Catalog object ID must not be empty");
+    }
+}
+
+// Which Square to talk to, and how patiently- per platform.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SquareClientConfig {
+    #[serde(default)]
+    pub(crate) environment: SquareEnvironment,
+    #[serde(default = "default_timeout")]
+    pub(crate) timeout: Duration, // Per request- whole seconds.
+    #[serde(default)]
+    pub(crate) retries: u32, // Retried by the client on transient errors, before our own retry loop.
+    #[serde(default = "default_retry_wait")]
+    pub(crate) retry_wait: Duration, // Before the first client retry- doubling after.
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub enum SquareEnvironment {
+    #[default]
+    Sandbox,
+    Production,
+    Custom(String), // Base URL of another Square- e.g. a local stand-in for CI.
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_retry_wait() -> Duration {
+    Duration::from_secs(1)
+}
+
+impl Default for SquareClientConfig {
+    fn default() -> Self {
+        SquareClientConfig { environment: SquareEnvironment::default(), timeout: default_timeout(), retries: 0, retry_wait: default_retry_wait() }
+    }
+}
+
+impl Validate for SquareClientConfig {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        if let SquareEnvironment::Custom(url) = &self.environment {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                issue(issues, format!("{path}.environment.Custom"), "Custom base URL must start with https:// or http://");
+            }
+        }
+        if self.timeout < Duration::from_secs(1) {
+            issue(issues, format!("{path}.timeout"), "Timeout must be at least a second");
+        }
+        if self.retries > 0 && self.retry_wait.is_zero() {
+            issue(issues, format!("{path}.retry_wait"), "Retry wait must be greater than zero when retrying");
+        }
+    }
+}
 
 #[derive(PartialEq, PartialOrd, Copy, Clone, Debug)]
 pub struct SquareMetadata {
@@ -0,0 +1,164 @@
+use std::time::Duration;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use crate::real_world::credentials::{Credential, Secret};
+use crate::real_world::http::{hmac_sha256_base64, serve, verify_hmac_sha256_base64, Request, Response};
+use crate::real_world::square::Target;
+use crate::validation::{issue, Validate, ValidationIssue};
+
+pub const SIGNATURE_HEADER: &str = "x-square-hmacsha256-signature";
+pub const COUNT_UPDATED: &str = "inventory.count.updated";
+
+// Square inventory.count.updated webhooks- each wakes the worker to fetch at once, rather than after its backoff.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SquareWebhookConfig {
+    pub(crate) listen: String, // Address to receive webhooks on, e.g. 0.0.0.0:8791
+    pub(crate) notification_url: String, // URL the subscription delivers to, exactly as registered- it is signed.
+    pub(crate) signature_key: Credential, // Subscription signature key.
+    #[serde(default = "default_fallback")]
+    pub(crate) fallback: Duration, // Fetch anyway after this long without a webhook- in case they stall.
+}
+
+fn default_fallback() -> Duration {
+    Duration::from_secs(30)
+}
+
+impl Validate for SquareWebhookConfig {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        if self.listen.parse::<std::net::SocketAddr>().is_err() {
+            issue(issues, format!("{path}.listen"), format!("{} is not a socket address", self.listen));
+        }
+        if !(self.notification_url.starts_with("https://") || self.notification_url.starts_with("http://")) {
+            issue(issues, format!("{path}.notification_url"), "Notification URL must start with https:// (or http:// for replays)");
+        }
+        self.signature_key.validate(&format!("{path}.signature_key"), issues);
+        if self.fallback.is_zero() {
+            issue(issues, format!("{path}.fallback"), "Fallback must be greater than zero");
+        }
+    }
+}
+
+// Only the fields needed to match a notification to our target.
+#[derive(Deserialize)]
+struct Notification {
+    r#type: String,
+    event_id: String,
+    data: NotificationData,
+}
+
+#[derive(Deserialize)]
+struct NotificationData {
+    object: NotificationObject,
+}
+
+#[derive(Deserialize)]
+struct NotificationObject {
+    #[serde(default)]
+    inventory_counts: Vec<NotifiedCount>,
+}
+
+#[derive(Deserialize)]
+struct NotifiedCount {
+    catalog_object_id: String,
+    location_id: String,
+}
+
+// Square signs the notification URL followed by the body.
+pub fn sign(signature_key: &str, notification_url: &str, body: &[u8]) -> String {
+    hmac_sha256_base64(signature_key, &[notification_url.as_bytes(), body].concat())
+}
+
+// Listen for webhooks, sending a wakeup for each verified count update of the target.
+pub async fn receive(name: &str, config: &SquareWebhookConfig, signature_key: Secret, target: Target) -> UnboundedReceiver<()> {
+    let (wakeup_tx, wakeup_rx) = unbounded_channel();
+    let listener = TcpListener::bind(&config.listen).await.expect(format!("{name} - Failed to listen on {}", config.listen).as_str());
+    let (name, config) = (name.to_string(), config.clone());
+
+    tokio::spawn(serve(listener, move |request: Request| {
+        let wakeup_tx = wakeup_tx.clone();
+        let (name, config, signature_key, target) = (name.clone(), config.clone(), signature_key.clone(), target.clone());
+        async move {
+            let message = [config.notification_url.as_bytes(), &request.body].concat();
+            let signed = request.header(SIGNATURE_HEADER)
+                .is_some_and(|signature| verify_hmac_sha256_base64(signature_key.expose(), &message, signature));
+            if request.method != "POST" || !signed {
+                warn!("{name} - Rejected unsigned webhook to {}", request.path);
+                return Response::status(401);
+            }
+            let Ok(notification) = serde_json::from_slice::<Notification>(&request.body) else {
+                return Response::status(400);
+            };
+            let ours = notification.data.object.inventory_counts.iter()
+                .any(|count| count.location_id == target.0 && count.catalog_object_id == target.1);
+            if notification.r#type == COUNT_UPDATED && ours {
+                info!("{name} - Notified by webhook {}", notification.event_id);
+                let _ = wakeup_tx.send(());
+            }
+            Response::status(200) // Acknowledge anything verified, so it is not retried.
+        }
+    }));
+    wakeup_rx
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Client;
+    use crate::real_world::credentials::Secrets;
+    use super::*;
+
+    const SIGNATURE_KEY: &str = "signature-key";
+    const NOTIFICATION_URL: &str = "https://sync.example.com/square";
+
+    // A receiver on a free port for the saved payload's target- its address, and its wakeups.
+    async fn receiver() -> (String, UnboundedReceiver<()>) {
+        let listen = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+        let config = SquareWebhookConfig {
+            listen: listen.clone(),
+            notification_url: NOTIFICATION_URL.to_string(),
+            signature_key: Credential::Inline(SIGNATURE_KEY.to_string()),
+            fallback: default_fallback(),
+        };
+        let signature_key = config.signature_key.resolve(&Secrets::default()).unwrap();
+        let target = ("L0ZSXYY4THT9D".to_string(), "F5FDIG3YCSZQXEXIRRLZLI6M".to_string());
+        (listen, receive("Square", &config, signature_key, target).await)
+    }
+
+    fn payload() -> serde_json::Value {
+        serde_json::from_str(include_str!("../../../webhooks/square/inventory_count_updated.json")).unwrap()
+    }
+
+    async fn deliver(listen: &str, body: &serde_json::Value, signature_key: &str) -> u16 {
+        let body = body.to_string();
+        Client::new().post(format!("http://{listen}/"))
+            .header(SIGNATURE_HEADER, sign(signature_key, NOTIFICATION_URL, body.as_bytes()))
+            .body(body)
+            .send().await.unwrap().status().as_u16()
+    }
+
+    #[tokio::test]
+    async fn wakes_on_signed_count_update() {
+        let (listen, mut wakeups) = receiver().await;
+        assert_eq!(deliver(&listen, &payload(), SIGNATURE_KEY).await, 200);
+        assert!(wakeups.try_recv().is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_signature() {
+        let (listen, mut wakeups) = receiver().await;
+        assert_eq!(deliver(&listen, &payload(), "wrong-key").await, 401);
+        assert!(wakeups.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn acknowledges_other_targets_without_waking() {
+        let (listen, mut wakeups) = receiver().await;
+        for field in ["location_id", "catalog_object_id"] {
+            let mut body = payload();
+            body["data"]["object"]["inventory_counts"][0][field] = "OTHER".into();
+            assert_eq!(deliver(&listen, &body, SIGNATURE_KEY).await, 200, "Verified, so not retried");
+            assert!(wakeups.try_recv().is_err(), "Another {field} is not ours");
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use log::{error, info, warn};
+use reqwest::Client;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use crate::intervals::Interval;
+use crate::observations::Observation;
+use crate::ordering::PlatformMetadata;
+use crate::predicates::DefinitionPredicate;
+use crate::real_world::connector::{Change, PlatformConnector, RecentChanges, REDELIVERY_WINDOW_HOURS};
+use crate::real_world::credentials::{Secret, Secrets};
+use crate::real_world::scheduler::{classify, Failure, Scheduler, Schedulers};
+use crate::real_world::http::{serve, verify_hmac_sha256_base64, Request, Response};
+use crate::real_world::woocommerce::{holds_stock, Order, Product, StockUpdate, WooCommerceConfig, WooCommerceMetadata, WooCommerceMode, API_PATH, GMT_FORMAT, ORDER_CREATED_TOPIC, ORDER_UPDATED_TOPIC, SIGNATURE_HEADER, TOPIC_HEADER};
+use crate::value::Value;
+
+// A verified order webhook, as received.
+struct Notification {
+    order: Order,
+    received: DateTime<Utc>,
+}
+
+// stock_quantity of one WooCommerce product (or variation).
+pub struct WooCommerceConnector {
+    name: String,
+    client: Client,
+    config: WooCommerceConfig,
+    consumer: (Secret, Secret), // Key and secret.
+    scheduler: Arc<Scheduler>, // Shared by platforms on the same store.
+    notifications: Option<UnboundedReceiver<Notification>>, // In orders mode.
+    orders: HashMap<u64, (DateTime<Utc>, Value)>, // Orders of our product- when their status last changed, and the units they hold from stock.
+}
+
+const ORDER_DAYS: i64 = 90; // Orders are rarely cancelled or refunded later than this- forgotten after, to bound memory.
+
+impl WooCommerceConnector {
+    // Starts the webhook receiver, in orders mode.
+    pub async fn new(name: String, config: WooCommerceConfig, secrets: &Secrets, schedulers: &mut Schedulers) -> WooCommerceConnector {
+        let consumer = (
+            config.consumer_key.resolve(secrets).unwrap_or_else(|e| panic!("{name} - Consumer key: {e}")),
+            config.consumer_secret.resolve(secrets).unwrap_or_else(|e| panic!("{name} - Consumer secret: {e}")),
+        );
+        let scheduler = schedulers.account(format!("woocommerce:{}", consumer.0.fingerprint()), &name);
+        let notifications = match &config.mode {
+            WooCommerceMode::Polling(_) => None,
+            WooCommerceMode::Orders { listen, secret, .. } => {
+                let (notification_tx, notification_rx) = unbounded_channel();
+                let listener = TcpListener::bind(listen).await.expect(format!("{name} - Failed to listen on {listen}").as_str());
+                let secret = secret.resolve(secrets).unwrap_or_else(|e| panic!("{name} - Webhook secret: {e}"));
+
+                tokio::spawn(serve(listener, move |request: Request| {
+                    let notification_tx = notification_tx.clone();
+                    let secret = secret.clone();
+                    async move {
+                        // WooCommerce pings new webhooks with an unsigned webhook_id=... form- acknowledge it.
+                        if request.header(TOPIC_HEADER).is_none() {
+                            return Response::status(200);
+                        }
+                        let signed = request.header(SIGNATURE_HEADER)
+                            .is_some_and(|signature| verify_hmac_sha256_base64(secret.expose(), &request.body, signature));
+                        if request.method != "POST" || !signed {
+                            warn!("WooCommerce - Rejected unsigned webhook to {}", request.path);
+                            return Response::status(401);
+                        }
+                        if !matches!(request.header(TOPIC_HEADER), Some(ORDER_CREATED_TOPIC | ORDER_UPDATED_TOPIC)) {
+                            return Response::status(200); // Acknowledge, so it is not retried.
+                        }
+                        match serde_json::from_slice::<Order>(&request.body) {
+                            Ok(order) => {
+                                let _ = notification_tx.send(Notification { order, received: Utc::now() });
+                                Response::status(200)
+                            }
+                            Err(_) => Response::status(400),
+                        }
+                    }
+                }));
+                Some(notification_rx)
+            }
+        };
+
+        WooCommerceConnector { name, client: Client::new(), config, consumer, scheduler, notifications, orders: HashMap::new() }
+    }
+
+    // The product, with when the request was sent and replied to.
+    async fn product(&self) -> Result<(Product, DateTime<Utc>, DateTime<Utc>), String> {
+        let url = self.url(&format!("products/{}", self.config.product_id));
+        let (client, consumer, url) = (&self.client, &self.consumer, &url);
+        self.scheduler.call(&self.name, || async move {
+            let sent = Utc::now();
+            let response = classify(client.get(url).basic_auth(consumer.0.expose(), Some(consumer.1.expose())).send().await)?;
+            let product = response.json::<Product>().await.map_err(|e| Failure::Retryable(format!("Malformed reply: {e}")))?;
+            Ok((product, sent, Utc::now()))
+        }).await
+    }
+
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}{API_PATH}/{endpoint}", self.config.store_url.trim_end_matches('/'))
+    }
+
+    // Units of our product sold in an order.
+    fn quantity_sold(&self, order: &Order) -> Value {
+        order.line_items.iter()
+            .filter(|item| item.product_id == self.config.product_id || item.variation_id == self.config.product_id)
+            .map(|item| item.quantity)
+            .sum()
+    }
+
+    // Our product taken from stock as an order is paid or held, or returned as it is cancelled or refunded- None for redeliveries.
+    // Without an observation for orders without it, for stale deliveries, and for updates which do not move stock.
+    // Orders taken before we started are never returned.
+    fn parse_notification(&mut self, notification: Notification, cursor: &mut RecentChanges) -> Option<Change> {
+        let order = &notification.order;
+        let modified = order.date_modified_gmt.as_deref().unwrap_or(&order.date_created_gmt);
+        let id = format!("{}-{}-{modified}", order.id, order.status); // Each status change is a change- its redeliveries are not.
+        let mut change = Change { id, at: notification.received, observation: None };
+        if !cursor.first_sight(&change) {
+            return None; // Failed deliveries are retried- each status change moves stock once.
+        }
+
+        let sold = self.quantity_sold(order);
+        if sold == 0 {
+            return Some(change);
+        }
+        let Ok(at) = NaiveDateTime::parse_from_str(modified, GMT_FORMAT) else {
+            error!("{} - Malformed order: {:?}", self.name, order);
+            return Some(change);
+        };
+        let at = at.and_utc();
+        let (latest, held) = self.orders.get(&order.id).copied().unwrap_or((at, 0));
+        if latest > at {
+            warn!("{} - Skipping stale delivery of order {} ({} at {at})", self.name, order.id, order.status);
+            return Some(change); // A newer status already decided what the order holds.
+        }
+        let delta = match (holds_stock(&order.status), held) {
+            (true, 0) => -sold,
+            (false, held) => held,
+            (true, _) => 0, // Already taken.
+        };
+        self.orders.insert(order.id, (at, held - delta));
+        if delta == 0 {
+            return Some(change);
+        }
+
+        let tolerance = match &self.config.mode {
+            WooCommerceMode::Orders { clock_tolerance, .. } => *clock_tolerance,
+            WooCommerceMode::Polling(_) => Duration::ZERO,
+        };
+        // Stock moves as the order's status changes- and certainly before we received it.
+        let earliest = (at - tolerance).min(notification.received);
+        change.at = at;
+        change.observation = Some(Observation {
+            interval: Interval(earliest, notification.received),
+            definition_predicate: DefinitionPredicate::AllMut(delta),
+            source: self.name.clone(),
+            platform_metadata: self.metadata(at),
+        });
+        Some(change)
+    }
+}
+
+impl PlatformConnector for WooCommerceConnector {
+    type Cursor = RecentChanges; // Order status changes delivered within the redelivery window.
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn read_count(&mut self) -> Result<(Value, DateTime<Utc>, DateTime<Utc>), String> {
+        let (product, sent, replied) = self.product().await?;
+        if !product.manage_stock {
+            warn!("{} - Product {} does not manage stock!", self.name, product.id);
+        }
+        Ok((product.stock_quantity.unwrap_or(0), sent, replied))
+    }
+
+    // The product's, rather than its stock's- WooCommerce does not say when stock alone changed.
+    async fn last_changed(&mut self) -> Result<Option<DateTime<Utc>>, String> {
+        let (product, _, _) = self.product().await?;
+        Ok(product.date_modified_gmt
+            .and_then(|modified| NaiveDateTime::parse_from_str(&modified, GMT_FORMAT).ok())
+            .map(|modified| modified.and_utc()))
+    }
+
+    async fn changes_since(&mut self, mut cursor: RecentChanges) -> Result<(Vec<Change>, RecentChanges), String> {
+        let mut received = vec![];
+        if let Some(notifications) = self.notifications.as_mut() {
+            while let Ok(notification) = notifications.try_recv() {
+                received.push(notification);
+            }
+        }
+        let changes = received.into_iter()
+            .filter_map(|notification| self.parse_notification(notification, &mut cursor))
+            .collect();
+        cursor.forget_before(Utc::now() - TimeDelta::hours(REDELIVERY_WINDOW_HOURS));
+        let horizon = Utc::now() - TimeDelta::days(ORDER_DAYS);
+        self.orders.retain(|_, (modified, _)| *modified >= horizon);
+        Ok((changes, cursor))
+    }
+
+    fn initial_cursor(&self) -> RecentChanges {
+        RecentChanges::default()
+    }
+
+    // Writes do not create orders- so are never read back as changes.
+    async fn write(&mut self, value: Value) -> Result<(), String> {
+        let url = self.url(&format!("products/{}", self.config.product_id));
+        let (client, consumer, url) = (&self.client, &self.consumer, &url);
+        self.scheduler.call(&self.name, || async move {
+            classify(client.put(url).basic_auth(consumer.0.expose(), Some(consumer.1.expose())).json(&StockUpdate { stock_quantity: value }).send().await)
+        }).await?;
+        info!("{} - Wrote Value: {:?}!", self.name, value);
+        Ok(())
+    }
+
+    fn metadata(&self, timestamp: DateTime<Utc>) -> PlatformMetadata {
+        PlatformMetadata::WooCommerce(WooCommerceMetadata { timestamp })
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::Utc;
+use log::{error, info};
+use reqwest::Client;
+use serde::Deserialize;
+use tokio::net::TcpListener;
+use uuid::Uuid;
+use crate::real_world::http::{hmac_sha256_base64, serve, Request, Response};
+use crate::real_world::woocommerce::{holds_stock, LineItem, Order, Product, StockUpdate, API_PATH, DELIVERY_ID_HEADER, GMT_FORMAT, ORDER_CREATED_TOPIC, ORDER_UPDATED_TOPIC, SIGNATURE_HEADER, TOPIC_HEADER};
+
+// An order placed against the mock- as a customer would.
+#[derive(Deserialize)]
+struct NewOrder {
+    line_items: Vec<LineItem>,
+    #[serde(default = "default_status")]
+    status: String,
+}
+
+fn default_status() -> String {
+    "processing".to_string()
+}
+
+// A change to an order's status- as the store's staff would make.
+#[derive(Deserialize)]
+struct OrderUpdate {
+    status: String,
+}
+
+// In-memory stand-in for the WooCommerce products and orders API- so the connector can be run without a store.
+// Products are created with stock 0 when first read. Orders take stock while processing, on-hold or completed, and return it
+// as they are cancelled or refunded- each sending a signed order.created or order.updated webhook, if configured.
+pub struct MockWooCommerce {
+    authorization: String, // Expected Authorization header.
+    webhook: Option<(String, String)>, // URL and secret.
+    products: Mutex<HashMap<u64, Product>>,
+    orders: Mutex<HashMap<u64, Order>>,
+    next_order_id: AtomicU64,
+    client: Client,
+}
+
+impl MockWooCommerce {
+    pub fn new(consumer_key: &str, consumer_secret: &str, webhook: Option<(String, String)>) -> MockWooCommerce {
+        MockWooCommerce {
+            authorization: format!("Basic {}", STANDARD.encode(format!("{consumer_key}:{consumer_secret}"))),
+            webhook,
+            products: Mutex::new(HashMap::new()),
+            orders: Mutex::new(HashMap::new()),
+            next_order_id: AtomicU64::new(1),
+            client: Client::new(),
+        }
+    }
+
+    // Serve until the listener fails.
+    pub async fn run(self, listener: TcpListener) {
+        let mock = Arc::new(self);
+        serve(listener, move |request| {
+            let mock = mock.clone();
+            async move { mock.handle(request).await }
+        }).await
+    }
+
+    async fn handle(&self, request: Request) -> Response {
+        if request.header("Authorization") != Some(self.authorization.as_str()) {
+            return Response::json(401, r#"{"code":"woocommerce_rest_cannot_view","message":"Sorry, you cannot list resources."}"#);
+        }
+
+        let endpoint = request.path.strip_prefix(API_PATH).unwrap_or("");
+        let product_id = endpoint.strip_prefix("/products/").and_then(|id| id.parse::<u64>().ok());
+        let order_id = endpoint.strip_prefix("/orders/").and_then(|id| id.parse::<u64>().ok());
+        match (request.method.as_str(), product_id, order_id, endpoint) {
+            ("GET", Some(id), _, _) => {
+                let product = self.products.lock().unwrap().entry(id).or_insert_with(|| product(id, 0)).clone();
+                Response::json(200, serde_json::to_string(&product).unwrap())
+            }
+            ("PUT", Some(id), _, _) => match serde_json::from_slice::<StockUpdate>(&request.body) {
+                Ok(update) => {
+                    let updated = product(id, update.stock_quantity);
+                    self.products.lock().unwrap().insert(id, updated.clone());
+                    info!("MockWooCommerce - Product now {:?}", updated);
+                    Response::json(200, serde_json::to_string(&updated).unwrap())
+                }
+                Err(e) => Response::json(400, format!(r#"{{"code":"rest_invalid_param","message":"{e}"}}"#)),
+            },
+            ("POST", _, _, "/orders") => match serde_json::from_slice::<NewOrder>(&request.body) {
+                Ok(new_order) => self.place(new_order.line_items, new_order.status),
+                Err(e) => Response::json(400, format!(r#"{{"code":"rest_invalid_param","message":"{e}"}}"#)),
+            },
+            ("PUT", _, Some(id), _) => match serde_json::from_slice::<OrderUpdate>(&request.body) {
+                Ok(update) => self.update(id, update.status),
+                Err(e) => Response::json(400, format!(r#"{{"code":"rest_invalid_param","message":"{e}"}}"#)),
+            },
+            _ => Response::status(404),
+        }
+    }
+
+    // Place an order- taking stock of each item if its status holds stock. WooCommerce allows backorders, so it may go negative.
+    fn place(&self, line_items: Vec<LineItem>, status: String) -> Response {
+        let now = Utc::now().format(GMT_FORMAT).to_string();
+        let order = Order {
+            id: self.next_order_id.fetch_add(1, Ordering::Relaxed),
+            status,
+            date_created_gmt: now.clone(),
+            date_modified_gmt: Some(now),
+            line_items,
+        };
+        if holds_stock(&order.status) {
+            self.restock(&order.line_items, -1);
+        }
+        self.orders.lock().unwrap().insert(order.id, order.clone());
+        self.notify(ORDER_CREATED_TOPIC, &order);
+        Response::json(201, serde_json::to_string(&order).unwrap())
+    }
+
+    // Change an order's status- taking or returning its stock where that moves it in or out of holding stock.
+    fn update(&self, id: u64, status: String) -> Response {
+        let order = {
+            let mut orders = self.orders.lock().unwrap();
+            let Some(order) = orders.get_mut(&id) else {
+                return Response::json(404, r#"{"code":"woocommerce_rest_shop_order_invalid_id","message":"Invalid ID."}"#);
+            };
+            match (holds_stock(&order.status), holds_stock(&status)) {
+                (false, true) => self.restock(&order.line_items, -1),
+                (true, false) => self.restock(&order.line_items, 1),
+                _ => {}
+            }
+            order.status = status;
+            order.date_modified_gmt = Some(Utc::now().format(GMT_FORMAT).to_string());
+            order.clone()
+        };
+        self.notify(ORDER_UPDATED_TOPIC, &order);
+        Response::json(200, serde_json::to_string(&order).unwrap())
+    }
+
+    // Adjust stock of each item by its quantity, in the given direction.
+    fn restock(&self, line_items: &[LineItem], direction: i64) {
+        let mut products = self.products.lock().unwrap();
+        for item in line_items {
+            let id = if item.variation_id != 0 { item.variation_id } else { item.product_id };
+            let current = products.entry(id).or_insert_with(|| product(id, 0));
+            *current = product(id, current.stock_quantity.unwrap_or(0) + direction * item.quantity);
+            info!("MockWooCommerce - Product now {:?}", current);
+        }
+    }
+
+    // Send the order as a signed webhook, if configured- after the reply, as WooCommerce does.
+    fn notify(&self, topic: &'static str, order: &Order) {
+        let Some((url, secret)) = &self.webhook else {
+            return;
+        };
+        let body = serde_json::to_string(order).unwrap();
+        let sent = self.client.post(url)
+            .header(TOPIC_HEADER, topic)
+            .header(SIGNATURE_HEADER, hmac_sha256_base64(secret, body.as_bytes()))
+            .header(DELIVERY_ID_HEADER, Uuid::new_v4().to_string())
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send();
+        tokio::spawn(async move {
+            if let Err(e) = sent.await {
+                error!("MockWooCommerce - Webhook failed: {e}");
+            }
+        });
+    }
+}
+
+fn product(id: u64, stock_quantity: i64) -> Product {
+    Product { id, manage_stock: true, stock_quantity: Some(stock_quantity), date_modified_gmt: Some(Utc::now().format(GMT_FORMAT).to_string()) }
+}
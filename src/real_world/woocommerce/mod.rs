@@ -0,0 +1,107 @@
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::real_world::connector::PollingInterpretation;
+use crate::real_world::credentials::Credential;
+use crate::validation::{issue, Validate, ValidationIssue};
+
+pub mod connector;
+pub mod mock;
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WooCommerceConfig {
+    pub(crate) store_url: String, // e.g. https://my-store.example- or a mock server.
+    pub(crate) consumer_key: Credential, // REST API key (WooCommerce > Settings > Advanced > REST API).
+    pub(crate) consumer_secret: Credential,
+    pub(crate) product_id: u64, // Product (or variation) whose stock_quantity to sync.
+    pub(crate) backoff: Duration, // Between polls, or between draining received webhooks.
+    pub(crate) mode: WooCommerceMode,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum WooCommerceMode {
+    Polling(PollingInterpretation), // Poll stock_quantity, as for Square.
+    Orders {
+        listen: String, // Address to receive order.created and order.updated webhooks on, e.g. 0.0.0.0:8789
+        secret: Credential, // Webhook secret, which signs deliveries.
+        #[serde(default = "default_clock_tolerance")]
+        clock_tolerance: Duration, // How far the store's order dates may run ahead of our clock.
+    },
+}
+
+fn default_clock_tolerance() -> Duration {
+    Duration::from_secs(1)
+}
+
+#[derive(PartialEq, PartialOrd, Copy, Clone, Debug)]
+pub struct WooCommerceMetadata {
+    pub(crate) timestamp: DateTime<Utc> // Poll sent time, or when the order's status last changed.
+}
+
+impl Validate for WooCommerceConfig {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        if !(self.store_url.starts_with("https://") || self.store_url.starts_with("http://")) {
+            issue(issues, format!("{path}.store_url"), "Store URL must start with https:// (or http:// for a mock)");
+        }
+        self.consumer_key.validate(&format!("{path}.consumer_key"), issues);
+        self.consumer_secret.validate(&format!("{path}.consumer_secret"), issues);
+        if self.backoff.is_zero() {
+            issue(issues, format!("{path}.backoff"), "Backoff must be greater than zero");
+        }
+        if let WooCommerceMode::Orders { listen, secret, .. } = &self.mode {
+            if listen.parse::<std::net::SocketAddr>().is_err() {
+                issue(issues, format!("{path}.mode.Orders.listen"), format!("{listen} is not a socket address"));
+            }
+            secret.validate(&format!("{path}.mode.Orders.secret"), issues);
+        }
+    }
+}
+
+// REST API (wc/v3) payloads- shared by the connector and the mock. Unused fields are ignored.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Product {
+    pub(crate) id: u64,
+    pub(crate) manage_stock: bool,
+    pub(crate) stock_quantity: Option<i64>, // None when stock is not managed.
+    #[serde(default)]
+    pub(crate) date_modified_gmt: Option<String>, // e.g. 2017-03-22T16:28:02- as date_created_gmt.
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StockUpdate {
+    pub(crate) stock_quantity: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Order {
+    pub(crate) id: u64,
+    pub(crate) status: String,
+    pub(crate) date_created_gmt: String, // e.g. 2017-03-22T16:28:02- UTC, without an offset.
+    #[serde(default)]
+    pub(crate) date_modified_gmt: Option<String>, // Ditto- when its status last changed, for order.updated.
+    pub(crate) line_items: Vec<LineItem>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LineItem {
+    pub(crate) product_id: u64,
+    #[serde(default)]
+    pub(crate) variation_id: u64, // 0 when not a variation.
+    pub(crate) quantity: i64,
+}
+
+pub const API_PATH: &str = "/wp-json/wc/v3";
+pub const TOPIC_HEADER: &str = "X-WC-Webhook-Topic";
+pub const SIGNATURE_HEADER: &str = "X-WC-Webhook-Signature";
+pub const DELIVERY_ID_HEADER: &str = "X-WC-Webhook-Delivery-ID";
+pub const ORDER_CREATED_TOPIC: &str = "order.created";
+pub const ORDER_UPDATED_TOPIC: &str = "order.updated";
+pub const GMT_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+// Whether an order in this status has taken its items from stock- WooCommerce reduces stock once an order is paid or held,
+// and restores it as the order is cancelled, refunded, or returned to pending.
+pub fn holds_stock(status: &str) -> bool {
+    matches!(status, "processing" | "on-hold" | "completed")
+}
@@ -0,0 +1,258 @@
+use std::time::Duration;
+use reqwest::Client;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::unbounded_channel;
+use tokio::time::sleep;
+use crate::predicates::DefinitionPredicate;
+use crate::real_world::connector::{Change, PlatformConnector, PollingInterpretation, RecentChanges};
+use crate::real_world::credentials::{Credential, Secrets};
+use crate::real_world::http::hmac_sha256_base64;
+use crate::real_world::scheduler::{SchedulerConfig, Schedulers};
+use crate::real_world::woocommerce::connector::WooCommerceConnector;
+use crate::real_world::woocommerce::mock::MockWooCommerce;
+use crate::real_world::woocommerce::{Order, Product, WooCommerceConfig, WooCommerceMode, API_PATH, ORDER_CREATED_TOPIC, ORDER_UPDATED_TOPIC, SIGNATURE_HEADER, TOPIC_HEADER};
+
+const KEY: &str = "ck_test";
+const SECRET: &str = "cs_test";
+const WEBHOOK_SECRET: &str = "webhook-secret";
+const PRODUCT: u64 = 794;
+
+// Mock on a free port, sending webhooks to the given address- its base URL.
+async fn mock(webhook_to: Option<&str>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let webhook = webhook_to.map(|address| (format!("http://{address}/"), WEBHOOK_SECRET.to_string()));
+    tokio::spawn(MockWooCommerce::new(KEY, SECRET, webhook).run(listener));
+    url
+}
+
+fn free_address() -> String {
+    std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string()
+}
+
+async fn connector(store_url: String, mode: WooCommerceMode) -> WooCommerceConnector {
+    let config = WooCommerceConfig {
+        store_url,
+        consumer_key: Credential::Inline(KEY.to_string()),
+        consumer_secret: Credential::Inline(SECRET.to_string()),
+        product_id: PRODUCT,
+        backoff: Duration::from_millis(10),
+        mode,
+    };
+    let (status_tx, _) = unbounded_channel();
+    let mut schedulers = Schedulers::new(SchedulerConfig::default(), status_tx);
+    WooCommerceConnector::new("WooCommerce".to_string(), config, &Secrets::default(), &mut schedulers).await
+}
+
+fn orders(listen: &str) -> WooCommerceMode {
+    WooCommerceMode::Orders { listen: listen.to_string(), secret: Credential::Inline(WEBHOOK_SECRET.to_string()), clock_tolerance: Duration::from_secs(1) }
+}
+
+// Place an order as a customer would- through the mock, not the connector. Its id.
+async fn place(store_url: &str, quantity: i64, status: &str) -> u64 {
+    let body = serde_json::json!({ "line_items": [{ "product_id": PRODUCT, "quantity": quantity }], "status": status });
+    let response = Client::new().post(format!("{store_url}{API_PATH}/orders"))
+        .basic_auth(KEY, Some(SECRET)).json(&body).send().await.unwrap();
+    assert!(response.status().is_success());
+    response.json::<Order>().await.unwrap().id
+}
+
+// Change an order's status as the store's staff would.
+async fn set_status(store_url: &str, order: u64, status: &str) {
+    let response = Client::new().put(format!("{store_url}{API_PATH}/orders/{order}"))
+        .basic_auth(KEY, Some(SECRET)).json(&serde_json::json!({ "status": status })).send().await.unwrap();
+    assert!(response.status().is_success());
+}
+
+// Changes received within a few seconds.
+async fn received(connector: &mut WooCommerceConnector, cursor: &mut RecentChanges) -> Vec<Change> {
+    for _ in 0..50 {
+        let changes;
+        (changes, *cursor) = connector.changes_since(cursor.clone()).await.unwrap();
+        if !changes.is_empty() {
+            return changes;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    vec![]
+}
+
+fn delta(change: &Change) -> Option<i64> {
+    match change.observation.as_ref()?.definition_predicate {
+        DefinitionPredicate::AllMut(delta) => Some(delta),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn reads_stock_quantity() {
+    let store_url = mock(None).await;
+    place(&store_url, 4, "processing").await;
+    let mut connector = connector(store_url, WooCommerceMode::Polling(PollingInterpretation::Mutation)).await;
+
+    let (value, sent, replied) = connector.read_count().await.unwrap();
+    assert_eq!(value, -4, "Backordered");
+    assert!(sent <= replied);
+    assert!(connector.last_changed().await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn writes_through_put() {
+    let store_url = mock(None).await;
+    let mut connector = connector(store_url.clone(), WooCommerceMode::Polling(PollingInterpretation::Mutation)).await;
+    connector.write(7).await.unwrap();
+
+    let product: Product = Client::new().get(format!("{store_url}{API_PATH}/products/{PRODUCT}"))
+        .basic_auth(KEY, Some(SECRET)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(product.stock_quantity, Some(7));
+}
+
+#[tokio::test]
+async fn observes_signed_orders() {
+    let listen = free_address();
+    let store_url = mock(Some(&listen)).await;
+    let mut connector = connector(store_url.clone(), orders(&listen)).await;
+    let mut cursor = connector.initial_cursor();
+
+    place(&store_url, 2, "processing").await;
+    let changes = received(&mut connector, &mut cursor).await;
+    assert_eq!(changes.len(), 1);
+    assert_eq!(delta(&changes[0]), Some(-2));
+}
+
+#[tokio::test]
+async fn restores_cancelled_orders() {
+    let listen = free_address();
+    let store_url = mock(Some(&listen)).await;
+    let mut connector = connector(store_url.clone(), orders(&listen)).await;
+    let mut cursor = connector.initial_cursor();
+
+    let order = place(&store_url, 2, "processing").await;
+    assert_eq!(delta(&received(&mut connector, &mut cursor).await[0]), Some(-2));
+    set_status(&store_url, order, "cancelled").await;
+    let changes = received(&mut connector, &mut cursor).await;
+    assert_eq!(changes.len(), 1);
+    assert_eq!(delta(&changes[0]), Some(2));
+}
+
+#[tokio::test]
+async fn observes_pending_orders_once_paid() {
+    let listen = free_address();
+    let store_url = mock(Some(&listen)).await;
+    let mut connector = connector(store_url.clone(), orders(&listen)).await;
+    let mut cursor = connector.initial_cursor();
+
+    let order = place(&store_url, 3, "pending").await;
+    let changes = received(&mut connector, &mut cursor).await;
+    assert_eq!(changes.len(), 1, "The order is received");
+    assert!(changes[0].observation.is_none(), "But holds no stock yet");
+
+    set_status(&store_url, order, "processing").await;
+    assert_eq!(delta(&received(&mut connector, &mut cursor).await[0]), Some(-3));
+    set_status(&store_url, order, "completed").await;
+    let changes = received(&mut connector, &mut cursor).await;
+    assert!(changes[0].observation.is_none(), "Completing an order already holding stock moves none");
+}
+
+#[tokio::test]
+async fn acknowledges_pings_and_other_topics() {
+    let listen = free_address();
+    let mut connector = connector(mock(None).await, orders(&listen)).await;
+    let mut cursor = connector.initial_cursor();
+    let client = Client::new();
+
+    let ping = client.post(format!("http://{listen}/")).body("webhook_id=1").send().await.unwrap();
+    assert_eq!(ping.status(), 200);
+
+    let body = serde_json::json!({ "id": PRODUCT, "manage_stock": true, "stock_quantity": 1 }).to_string();
+    let product_updated = client.post(format!("http://{listen}/"))
+        .header(TOPIC_HEADER, "product.updated")
+        .header(SIGNATURE_HEADER, hmac_sha256_base64(WEBHOOK_SECRET, body.as_bytes()))
+        .body(body)
+        .send().await.unwrap();
+    assert_eq!(product_updated.status(), 200);
+
+    assert!(received(&mut connector, &mut cursor).await.is_empty());
+}
+
+#[tokio::test]
+async fn rejects_unsigned_orders() {
+    let listen = free_address();
+    let mut connector = connector(mock(None).await, orders(&listen)).await;
+    let mut cursor = connector.initial_cursor();
+
+    let body = serde_json::json!({
+        "id": 1, "status": "processing", "date_created_gmt": "2024-01-01T00:00:00",
+        "line_items": [{ "product_id": PRODUCT, "quantity": 1 }]
+    }).to_string();
+    let send = |signature: Option<String>| {
+        let request = Client::new().post(format!("http://{listen}/")).header(TOPIC_HEADER, ORDER_CREATED_TOPIC).body(body.clone());
+        let request = match signature {
+            Some(signature) => request.header(SIGNATURE_HEADER, signature),
+            None => request,
+        };
+        request.send()
+    };
+    assert_eq!(send(None).await.unwrap().status(), 401);
+    assert_eq!(send(Some(hmac_sha256_base64("wrong-secret", body.as_bytes()))).await.unwrap().status(), 401);
+
+    assert!(received(&mut connector, &mut cursor).await.is_empty());
+}
+
+#[tokio::test]
+async fn skips_redeliveries() {
+    let listen = free_address();
+    let mut connector = connector(mock(None).await, orders(&listen)).await;
+    let mut cursor = connector.initial_cursor();
+
+    let body = serde_json::json!({
+        "id": 1, "status": "processing", "date_created_gmt": "2024-01-01T00:00:00",
+        "line_items": [{ "product_id": PRODUCT, "quantity": 1 }]
+    }).to_string();
+    for _ in 0..2 {
+        let delivery = Client::new().post(format!("http://{listen}/"))
+            .header(TOPIC_HEADER, ORDER_CREATED_TOPIC)
+            .header(SIGNATURE_HEADER, hmac_sha256_base64(WEBHOOK_SECRET, body.as_bytes()))
+            .body(body.clone())
+            .send().await.unwrap();
+        assert_eq!(delivery.status(), 200);
+    }
+
+    let changes = received(&mut connector, &mut cursor).await;
+    assert_eq!(changes.len(), 1);
+    assert_eq!(delta(&changes[0]), Some(-1));
+    assert!(received(&mut connector, &mut cursor).await.is_empty(), "Redelivered once only");
+}
+
+// Deliver a status change of order 1, signed as WooCommerce would.
+async fn deliver(listen: &str, status: &str, modified: &str) {
+    let body = serde_json::json!({
+        "id": 1, "status": status, "date_created_gmt": "2024-01-01T00:00:00", "date_modified_gmt": modified,
+        "line_items": [{ "product_id": PRODUCT, "quantity": 1 }]
+    }).to_string();
+    let delivery = Client::new().post(format!("http://{listen}/"))
+        .header(TOPIC_HEADER, ORDER_UPDATED_TOPIC)
+        .header(SIGNATURE_HEADER, hmac_sha256_base64(WEBHOOK_SECRET, body.as_bytes()))
+        .body(body)
+        .send().await.unwrap();
+    assert_eq!(delivery.status(), 200);
+}
+
+#[tokio::test]
+async fn skips_stale_statuses() {
+    let listen = free_address();
+    let mut connector = connector(mock(None).await, orders(&listen)).await;
+    let mut cursor = connector.initial_cursor();
+
+    deliver(&listen, "processing", "2024-01-01T00:00:00").await;
+    deliver(&listen, "cancelled", "2024-01-01T00:01:00").await;
+    let changes = received(&mut connector, &mut cursor).await;
+    assert_eq!(changes.iter().map(delta).collect::<Vec<_>>(), vec![Some(-1), Some(1)]);
+
+    deliver(&listen, "processing", "2024-01-01T00:00:00").await; // Redelivered.
+    deliver(&listen, "on-hold", "2024-01-01T00:00:30").await; // Delivered late.
+    deliver(&listen, "refunded", "2024-01-01T00:02:00").await;
+    let changes = received(&mut connector, &mut cursor).await;
+    assert_eq!(changes.len(), 2, "The redelivery is skipped");
+    assert!(changes.iter().all(|change| change.observation.is_none()), "Stock was already returned on cancellation");
+}
@@ -0,0 +1,42 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tai_time::MonotonicTime;
+use crate::validation::{issue, Validate, ValidationIssue};
+
+// Local clock of an interface host- skewed from the simulator's perfect clock.
+// local(t) = t + offset + drift * t
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ClockParameters {
+    #[serde(default)]
+    pub(crate) offset_ms: f64, // Constant offset, may be negative.
+    #[serde(default)]
+    pub(crate) drift_ppm: f64, // Parts per million gained (or lost, if negative) since simulation start.
+}
+
+impl ClockParameters {
+    pub fn now(&self, true_time: MonotonicTime) -> MonotonicTime {
+        if self.offset_ms == 0.0 && self.drift_ppm == 0.0 {
+            return true_time;
+        }
+
+        let elapsed = true_time.duration_since(MonotonicTime::EPOCH).as_secs_f64();
+        let skew = self.offset_ms / 1000.0 + self.drift_ppm / 1_000_000.0 * elapsed; // Seconds.
+        if skew >= 0.0 {
+            true_time + Duration::from_secs_f64(skew)
+        } else {
+            true_time - Duration::from_secs_f64(-skew)
+        }
+    }
+}
+
+impl Validate for ClockParameters {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        if !self.offset_ms.is_finite() {
+            issue(issues, format!("{path}.offset_ms"), "Clock offset must be finite");
+        }
+        // Beyond this, the local clock would run backwards.
+        if !(self.drift_ppm.is_finite() && self.drift_ppm > -1_000_000.0) {
+            issue(issues, format!("{path}.drift_ppm"), format!("Clock drift must be above -1000000ppm, got {}", self.drift_ppm));
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use crate::simulation::results::ScenarioSummary;
+
+// Comparison of two runs of the same scenarios- e.g. before and after a change to `merge_procedure`.
+
+pub const SIGNIFICANCE: f64 = 0.05; // Differences with p below this are reported as changes.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Improved,
+    Regression,
+    Unchanged, // No significant difference.
+}
+
+#[derive(Debug)]
+pub struct MetricComparison {
+    pub(crate) metric: &'static str,
+    pub(crate) baseline: String,
+    pub(crate) candidate: String,
+    pub(crate) p_value: Option<f64>, // None if there is too little data to test.
+    pub(crate) verdict: Verdict,
+}
+
+#[derive(Debug)]
+pub struct ScenarioComparison {
+    pub(crate) scenario: String,
+    pub(crate) metrics: Vec<MetricComparison>,
+}
+
+impl ScenarioComparison {
+    pub fn regressed(&self) -> bool {
+        self.metrics.iter().any(|m| m.verdict == Verdict::Regression)
+    }
+}
+
+// Compare scenarios present in both runs, by name. Lower is better for every metric.
+pub fn compare(baseline: &[ScenarioSummary], candidate: &[ScenarioSummary]) -> (Vec<ScenarioComparison>, Vec<String>) {
+    let baseline: BTreeMap<&str, &ScenarioSummary> = baseline.iter().map(|s| (s.scenario.as_str(), s)).collect();
+    let candidate: BTreeMap<&str, &ScenarioSummary> = candidate.iter().map(|s| (s.scenario.as_str(), s)).collect();
+
+    let mut comparisons = vec![];
+    let mut unmatched = vec![];
+    for (name, base) in &baseline {
+        let Some(cand) = candidate.get(name) else {
+            unmatched.push(name.to_string());
+            continue;
+        };
+        comparisons.push(ScenarioComparison {
+            scenario: name.to_string(),
+            metrics: vec![
+                compare_rate("Conflict rate", base.conflicts, base.iterations, cand.conflicts, cand.iterations),
+                compare_rate("Divergence rate", base.divergences, base.iterations, cand.divergences, cand.iterations),
+                compare_distribution("Convergence time", &base.convergence_times, &cand.convergence_times),
+            ],
+        });
+    }
+    unmatched.extend(candidate.keys().filter(|name| !baseline.contains_key(*name)).map(|name| name.to_string()));
+    (comparisons, unmatched)
+}
+
+fn verdict(p_value: Option<f64>, candidate_worse: bool) -> Verdict {
+    match p_value {
+        Some(p) if p < SIGNIFICANCE && candidate_worse => Verdict::Regression,
+        Some(p) if p < SIGNIFICANCE => Verdict::Improved,
+        _ => Verdict::Unchanged,
+    }
+}
+
+fn compare_rate(metric: &'static str, base_count: u64, base_n: u64, cand_count: u64, cand_n: u64) -> MetricComparison {
+    let rate = |count: u64, n: u64| if n == 0 { 0.0 } else { count as f64 / n as f64 };
+    let (base_rate, cand_rate) = (rate(base_count, base_n), rate(cand_count, cand_n));
+    let p_value = two_proportion_z_test(base_count, base_n, cand_count, cand_n);
+    MetricComparison {
+        metric,
+        baseline: format!("{base_rate:.4} ({base_count}/{base_n})"),
+        candidate: format!("{cand_rate:.4} ({cand_count}/{cand_n})"),
+        p_value,
+        verdict: verdict(p_value, cand_rate > base_rate),
+    }
+}
+
+fn compare_distribution(metric: &'static str, base: &[f64], cand: &[f64]) -> MetricComparison {
+    let describe = |samples: &[f64]| match median(samples) {
+        Some(median) => format!("median {median:.3}s (n={})", samples.len()),
+        None => "- (n=0)".to_string(),
+    };
+    let test = mann_whitney_u(base, cand);
+    MetricComparison {
+        metric,
+        baseline: describe(base),
+        candidate: describe(cand),
+        p_value: test.map(|(_, p)| p),
+        // U counts baseline samples above candidate samples- below half of all pairs means candidate is slower.
+        verdict: verdict(test.map(|(_, p)| p), test.is_some_and(|(u, _)| u < (base.len() * cand.len()) as f64 / 2.0)),
+    }
+}
+
+// Pooled two-sided z-test for a difference in proportions.
+pub fn two_proportion_z_test(x1: u64, n1: u64, x2: u64, n2: u64) -> Option<f64> {
+    if n1 == 0 || n2 == 0 {
+        return None;
+    }
+    let (n1, n2) = (n1 as f64, n2 as f64);
+    let pooled = (x1 + x2) as f64 / (n1 + n2);
+    let standard_error = (pooled * (1.0 - pooled) * (1.0 / n1 + 1.0 / n2)).sqrt();
+    if standard_error == 0.0 {
+        return Some(1.0); // Both rates are 0 (or both 1)- identical.
+    }
+    let z = (x1 as f64 / n1 - x2 as f64 / n2) / standard_error;
+    Some(2.0 * (1.0 - normal_cdf(z.abs())))
+}
+
+// Two-sided Mann-Whitney U test (normal approximation, tie-corrected). Returns (U of first sample, p).
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> Option<(f64, f64)> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let (n1, n2) = (a.len() as f64, b.len() as f64);
+
+    // Rank the pooled samples, giving ties their mean rank.
+    let mut pooled: Vec<(f64, bool)> = a.iter().map(|x| (*x, true)).chain(b.iter().map(|x| (*x, false))).collect();
+    pooled.sort_by(|x, y| x.0.total_cmp(&y.0));
+    let mut rank_sum_a = 0.0;
+    let mut tie_correction = 0.0;
+    let mut i = 0;
+    while i < pooled.len() {
+        let mut j = i;
+        while j + 1 < pooled.len() && pooled[j + 1].0 == pooled[i].0 {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        let ties = (j - i + 1) as f64;
+        tie_correction += ties.powi(3) - ties;
+        rank_sum_a += pooled[i..=j].iter().filter(|(_, from_a)| *from_a).count() as f64 * rank;
+        i = j + 1;
+    }
+
+    let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+    let n = n1 + n2;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - tie_correction / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return Some((u, 1.0)); // All samples equal.
+    }
+    let z = (u - n1 * n2 / 2.0) / variance.sqrt();
+    Some((u, 2.0 * (1.0 - normal_cdf(z.abs()))))
+}
+
+fn median(samples: &[f64]) -> Option<f64> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|x, y| x.total_cmp(y));
+    let mid = sorted.len() / 2;
+    Some(if sorted.len() % 2 == 0 { (sorted[mid - 1] + sorted[mid]) / 2.0 } else { sorted[mid] })
+}
+
+// Standard normal CDF, via the Abramowitz and Stegun (7.1.26) approximation of erf.
+pub fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let erf = 1.0 - (((((1.061405429 * t - 1.453152027) * t) + 1.421413741) * t - 0.284496736) * t + 0.254829592) * t * (-x * x).exp();
+    if z >= 0.0 { 0.5 * (1.0 + erf) } else { 0.5 * (1.0 - erf) }
+}
+
+// Per-scenario diff, with regressions marked.
+pub fn comparison_table(comparisons: &[ScenarioComparison]) -> String {
+    let mut table = format!("{:<40} {:<18} {:>26} {:>26} {:>9}  {}\n", "Scenario", "Metric", "Baseline", "Candidate", "p", "Verdict");
+    for comparison in comparisons {
+        for metric in &comparison.metrics {
+            table += &format!(
+                "{:<40} {:<18} {:>26} {:>26} {:>9}  {}\n",
+                comparison.scenario,
+                metric.metric,
+                metric.baseline,
+                metric.candidate,
+                metric.p_value.map(|p| format!("{p:.4}")).unwrap_or("-".to_string()),
+                match metric.verdict {
+                    Verdict::Regression => "<<< REGRESSION",
+                    Verdict::Improved => "improved",
+                    Verdict::Unchanged => "",
+                },
+            );
+        }
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: f64, expected: f64) -> bool {
+        (actual - expected).abs() < 1e-4
+    }
+
+    #[test]
+    fn normal_cdf_matches_tables() {
+        assert!(close(normal_cdf(0.0), 0.5));
+        assert!(close(normal_cdf(1.96), 0.9750));
+        assert!(close(normal_cdf(-1.96), 0.0250));
+        assert!(close(normal_cdf(1.0) + normal_cdf(-1.0), 1.0));
+    }
+
+    #[test]
+    fn z_test_of_differing_rates() {
+        // 45/100 against 30/100- pooled 0.375, z = 2.19.
+        let p = two_proportion_z_test(45, 100, 30, 100).unwrap();
+        assert!(close(p, 0.02846), "{p}");
+        assert!(close(two_proportion_z_test(30, 100, 45, 100).unwrap(), p), "Two-sided");
+    }
+
+    #[test]
+    fn z_test_without_samples_or_variance() {
+        assert_eq!(two_proportion_z_test(0, 0, 1, 10), None);
+        assert_eq!(two_proportion_z_test(1, 10, 0, 0), None);
+        assert_eq!(two_proportion_z_test(0, 10, 0, 20), Some(1.0), "Both rates 0");
+        assert_eq!(two_proportion_z_test(10, 10, 20, 20), Some(1.0), "Both rates 1");
+    }
+
+    #[test]
+    fn mann_whitney_with_ties() {
+        // U counts pairs with a above b, and half of those tied: 6.5 of 30. Ties of 2, 3 and 4 correct the variance to 29.18.
+        let (u, p) = mann_whitney_u(&[1.0, 2.0, 2.0, 3.0, 5.0], &[2.0, 3.0, 4.0, 4.0, 6.0, 7.0]).unwrap();
+        assert_eq!(u, 6.5);
+        assert!(close(p, 0.11561), "{p}");
+    }
+
+    #[test]
+    fn mann_whitney_without_samples_or_variance() {
+        assert_eq!(mann_whitney_u(&[], &[1.0]), None);
+        assert_eq!(mann_whitney_u(&[1.0], &[]), None);
+        assert_eq!(mann_whitney_u(&[3.0, 3.0], &[3.0, 3.0]), Some((2.0, 1.0)), "All samples equal");
+    }
+}
@@ -1,19 +1,40 @@
 use std::collections::HashMap;
 use std::time::Duration;
 use serde::{Deserialize, Serialize};
+use crate::allocation::AllocationConfig;
+use crate::interpreter::registry::PlatformRegistry;
 use tai_time::MonotonicTime;
 use crate::simulation::polling::r#unsafe::UnsafePollingConfig;
 use crate::simulation::polling::safe::SafePollingConfig;
 use crate::simulation::record::RecordConfig;
+use crate::simulation::expect::ExpectConfig;
+use crate::simulation::interpreter::interpreter::InterpreterParameters;
+use crate::simulation::script::ScriptConfig;
 use crate::value::Value;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimulationConfig {
+    #[serde(default)]
+    pub(crate) name: Option<String>, // Label for results and logs, defaults to file name (and index).
     pub(crate) initial_value: Value,
     #[serde(with="serde_monotonic_helper")]
     pub(crate) until: MonotonicTime,
     pub(crate) max_divergence_before_error: Duration,
     pub(crate) platforms: HashMap<String, PlatformConfig>,
+    #[serde(default)]
+    pub(crate) registry: PlatformRegistry, // Priority and trust of each platform, by name- same as real-world configs.
+    #[serde(default)]
+    pub(crate) allocation: AllocationConfig, // Between interpreted stock and each platform's written value.
+    #[serde(default)]
+    pub(crate) interpreter: InterpreterParameters, // Latency, processing time and crashes of the interpreter service.
+    #[serde(default)]
+    pub(crate) script: ScriptConfig, // Exact user actions and network delays, on top of the random users.
+    #[serde(default)]
+    pub(crate) soundness: bool, // Check every observation against the true events it reflects.
+    #[serde(default)]
+    pub(crate) seed: Option<u64>, // Iteration i runs with seed + i. Random if not given.
+    #[serde(default)]
+    pub(crate) expect: Option<ExpectConfig>, // Expected results, checked by `synchro check`.
 }
 
 pub mod serde_monotonic_helper {
@@ -31,7 +52,9 @@ pub mod serde_monotonic_helper {
     pub fn deserialize<'de, D>(d: D) -> Result<MonotonicTime, D::Error>
     where D: Deserializer<'de> {
         let secs = i64::deserialize(d)?;
-        Ok(MonotonicTime::from_chrono_date_time(&DateTime::from_timestamp(secs, 0).unwrap(), 0))
+        let date_time = DateTime::from_timestamp(secs, 0)
+            .ok_or_else(|| serde::de::Error::custom(format!("timestamp {secs} is out of range")))?;
+        Ok(MonotonicTime::from_chrono_date_time(&date_time, 0))
     }
 }
 
@@ -41,3 +64,15 @@ pub enum PlatformConfig {
     PollingUnsafe(UnsafePollingConfig),
     Record(RecordConfig),
 }
+
+
+impl PlatformConfig {
+    // Interface and its parameters- to say who is responsible for an unsound observation.
+    pub fn interface_description(&self) -> String {
+        match self {
+            PlatformConfig::PollingSafe(cfg) => format!("PollingSafe {:?}", cfg.interface_params),
+            PlatformConfig::PollingUnsafe(cfg) => format!("PollingUnsafe {:?}", cfg.interface_params),
+            PlatformConfig::Record(cfg) => format!("Record {:?}, {:?}", cfg.interface_params, cfg.platform_params),
+        }
+    }
+}
\ No newline at end of file
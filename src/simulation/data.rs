@@ -1,8 +1,10 @@
 use std::cmp::Ordering;
+use std::ops::Range;
 
 #[derive(Clone, Debug)]
 pub struct SimulationMetaData {
-    pub(crate) monotonic: u64
+    pub(crate) monotonic: u64,
+    pub(crate) truth: Range<u64>, // Tags of the platform's true events the observation reflects- for soundness checks only.
 }
 impl PartialEq for SimulationMetaData {
     fn eq(&self, _: &Self) -> bool {
@@ -1,25 +1,66 @@
+use std::collections::{BTreeMap, HashMap};
 use std::time::Duration;
 use log::{error, info, warn};
 use nexosim::ports::{EventBuffer, EventSlot};
+use rand::Rng;
 use tai_time::MonotonicTime;
+use crate::allocation::allocated;
 use crate::interpreter::history::History;
+use crate::observations::Observation;
+use crate::intervals::Interval;
 use crate::predicates::DefinitionPredicate;
-use crate::simulation::config::SimulationConfig;
-use crate::simulation::error::{DivergenceError, SimulationError};
+use crate::simulation::config::{PlatformConfig, SimulationConfig};
+use crate::simulation::error::{DivergenceError, SimulationError, UnexpectedOutcomeError};
+use crate::simulation::messages::UserAction;
 use crate::simulation::model::build_model;
-use crate::simulation::results::{SimulationResults, SimulationStatistics};
-pub type TruthRecord = (DefinitionPredicate, MonotonicTime);
+use crate::simulation::results::{IterationMetrics, SimulationResults, SimulationStatistics};
+use crate::simulation::soundness::SoundnessChecker;
+use crate::simulation::statistics::{mean_interval, wilson, StoppingRule, Z_95};
+use crate::simulation::timeseries::{Series, TimeSeriesWriter};
+use crate::value::Value;
+pub type TruthRecord = (String, DefinitionPredicate, MonotonicTime); // Platform the true event happened on, event, at.
+pub type ObservationRecord = Observation<MonotonicTime>;
+pub type DisplayRecord = (String, Value, MonotonicTime); // Platform, value now displayed, at.
+pub type LostUpdateRecord = (String, UserAction, MonotonicTime); // Platform, action erased by write, at.
+pub type RefusedSaleRecord = (String, Value, MonotonicTime); // Platform, units the channel refused to sell, at.
 
-fn iteration(simulation_config: &SimulationConfig) -> Result<Option<Duration>, SimulationError> {
+// Initial displayed value of each platform which models one. (Record platforms do not model writes.)
+fn initial_displays(simulation_config: &SimulationConfig) -> HashMap<String, Value> {
+    simulation_config.platforms.iter().filter_map(|(name, platform)| match platform {
+        PlatformConfig::PollingSafe(cfg) => Some((name.clone(), expected_display(simulation_config, name, cfg.initial_value))),
+        PlatformConfig::PollingUnsafe(cfg) => Some((name.clone(), expected_display(simulation_config, name, cfg.initial_value))),
+        PlatformConfig::Record(_) => None,
+    }).collect()
+}
+
+// Value a platform should display for the true stock- as allocated to its channel.
+fn expected_display(simulation_config: &SimulationConfig, name: &str, stock: Value) -> Value {
+    allocated(simulation_config.allocation.policy(name).as_ref(), stock)
+}
+
+fn iteration(
+    simulation_config: &SimulationConfig,
+    metrics: &mut IterationMetrics,
+    index: u64, // Iteration number, for the time series.
+    seed: u64, // Seeds all random models in the iteration.
+    mut timeseries: Option<&mut TimeSeriesWriter>,
+) -> Result<Vec<Duration>, SimulationError> {
     let mut truth_sink = EventBuffer::new(); // Get true event records.
+    let mut display_sink = EventBuffer::new(); // Get platform displayed values.
+    let mut lost_update_sink = EventBuffer::new(); // Get user actions erased by unsafe writes.
+    let mut refused_sale_sink = EventBuffer::new(); // Get sales refused by channels showing too few units.
+    let mut observation_sink = EventBuffer::new(); // Get observations sent by interfaces, to check their intervals.
     let mut found_slot = EventSlot::new(); // Where calculated values go for comparison.
-    let mut simulation = build_model(simulation_config, &mut truth_sink, &found_slot);
+    let mut simulation = build_model(simulation_config, seed, &mut truth_sink, &mut display_sink, &mut lost_update_sink, &mut refused_sale_sink, &mut observation_sink, &found_slot);
 
     // Error-Trace Capture
     let mut truth_records = vec![];
+    let mut truth_times: HashMap<String, Vec<MonotonicTime>> = HashMap::new(); // True event times per platform, in order (index is its tag).
+    let mut soundness = simulation_config.soundness.then(SoundnessChecker::default);
 
     // Statistics Capture
     let mut convergence_times = Vec::new();
+    let mut displayed = initial_displays(simulation_config);
 
     // Main Simulation Loop.
     let mut diverged_at = None; // When the simulation last diverged.
@@ -27,19 +68,87 @@ fn iteration(simulation_config: &SimulationConfig) -> Result<Option<Duration>, S
     let mut observed_value = None;
     // TODO: Detect Liveness.
 
+    // Start the time series from the initial values.
+    if let Some(timeseries) = timeseries.as_mut() {
+        timeseries.record(index, simulation.time(), Series::Truth, "", Some(true_value), "Initial".to_string());
+        for (name, value) in &displayed {
+            timeseries.record(index, simulation.time(), Series::Display, name, Some(*value), String::new());
+        }
+    }
+
     while simulation.time() < (simulation_config.until + Duration::from_secs(60)) {
+        let last_step = simulation.time();
         simulation.step()?; // Advance simulation.
 
+        // Platforms displaying a wrong value have done so since the last step.
+        for (name, value) in &displayed {
+            if *value != expected_display(simulation_config, name, true_value) {
+                *metrics.wrong_display_time.entry(name.clone()).or_default() += simulation.time().duration_since(last_step);
+            }
+        }
+
         // Consume and apply all true events at the moment when they occur.
-        for (event, at) in &mut truth_sink {
-            truth_records.push((event.clone(), at));
+        for (source, event, at) in &mut truth_sink {
+            // Units sold past zero are oversold.
+            if let DefinitionPredicate::AllMut(delta) = &event {
+                let after = true_value + delta;
+                if *delta < 0 && after < 0 {
+                    metrics.oversold_units += (-after).min(-delta) as u64;
+                }
+            }
             true_value = event.apply(Some(true_value)).unwrap(); // All true events are known, and defined for all inputs.
+            if let Some(timeseries) = timeseries.as_mut() {
+                timeseries.record(index, at, Series::Truth, &source, Some(true_value), format!("{event:?}"));
+            }
+            truth_times.entry(source.clone()).or_default().push(at);
+            truth_records.push((source, event, at));
             // debug!("True Event {:?} -> {} at {at:?}", event, true_value);
         }
 
+        // Consume platform displayed values.
+        for (name, value, at) in &mut display_sink {
+            if let Some(timeseries) = timeseries.as_mut() {
+                timeseries.record(index, at, Series::Display, &name, Some(value), String::new());
+            }
+            displayed.insert(name, value);
+        }
+
+        // An observation's interval must contain a true event of its platform- unless clock skew has pushed it out.
+        for observation in &mut observation_sink {
+            let Interval(start, end) = observation.interval;
+            let times = truth_times.get(&observation.source).map(|t| t.as_slice()).unwrap_or_default();
+            let first_after_start = times.partition_point(|t| *t < start);
+            if times.get(first_after_start).is_none_or(|t| *t > end) {
+                info!("Interval {:?} from {} excludes every true event of the platform", observation.interval, observation.source);
+                metrics.excluded_intervals += 1;
+            }
+
+            if let Some(checker) = soundness.as_mut() {
+                for violation in checker.check(&observation, &truth_times) {
+                    error!("Unsound observation from {} [{}]: {violation}",
+                        observation.source, simulation_config.platforms[&observation.source].interface_description());
+                    metrics.soundness_violations += 1;
+                }
+            }
+        }
+
+        // Consume user actions erased by unsafe writes.
+        metrics.lost_updates += (&mut lost_update_sink).count() as u64;
+
+        // Refused sales are lost where the true stock could have covered them.
+        for (_, units, _) in &mut refused_sale_sink {
+            metrics.lost_sales += units.min(true_value.max(0)) as u64;
+        }
+
         // Consume and log interpreted values.
         if let Some(observed) = found_slot.next() {
             // debug!("Value Observed: {:?}", observed);
+            if let Some(timeseries) = timeseries.as_mut() {
+                match &observed {
+                    Ok(value) => timeseries.record(index, simulation.time(), Series::Interpreter, "", Some(*value), String::new()),
+                    Err(conflict) => timeseries.record(index, simulation.time(), Series::Interpreter, "", None, conflict.reason.clone()),
+                }
+            }
             match observed {
                 // If value - update observed.
                 Ok(value) => {
@@ -80,33 +189,72 @@ fn iteration(simulation_config: &SimulationConfig) -> Result<Option<Duration>, S
         }))
     }
 
-    if convergence_times.is_empty() {
-        Ok(None)
-    } else {
-        Ok(Some(convergence_times.iter().sum::<Duration>() / convergence_times.len() as u32))
+    // Scripted scenarios may pin down the final interpreted value.
+    if let Some(expected) = simulation_config.script.expected_value {
+        if observed_value.unwrap_or(simulation_config.initial_value) != expected {
+            return Err(SimulationError::UnexpectedOutcome(UnexpectedOutcomeError {
+                expected,
+                observed: observed_value,
+            }))
+        }
     }
+
+    Ok(convergence_times)
 }
 
 pub fn driver(
     simulation_config: SimulationConfig, // The simulation to run.
-    iterations: u64, // Number of iterations.
+    iterations: u64, // Number of iterations- or the cap, with a stopping rule.
+    stopping: Option<StoppingRule>, // Stop early once the results are precise enough.
+    mut timeseries: Option<&mut TimeSeriesWriter>, // Where to export each iteration's trajectory, if anywhere.
 ) -> SimulationResults {
     let mut divergence = Vec::new();
     let mut conflicts = Vec::new();
     let mut convergence_times = Vec::new();
+    let mut convergence_events = Vec::new();
     let mut success = 0;
+    let mut oversold_units = 0;
+    let mut lost_updates = 0;
+    let mut wrong_display_time = BTreeMap::new();
+    let mut unexpected_outcomes = 0;
+    let mut excluded_intervals = 0;
+    let mut soundness_violations = 0;
+    let mut lost_sales = 0;
+    let mut completed = 0; // Iterations actually run.
 
     for i in 0..iterations {
         info!("Running Iteration {i}");
         println!("Running Iteration {i}");
 
-        match iteration(&simulation_config) {
+        // Fixed seeds make the whole run reproducible- otherwise the seed is logged to replay an iteration.
+        let seed = match simulation_config.seed {
+            Some(seed) => seed.wrapping_add(i),
+            None => rand::rng().random(),
+        };
+        info!("Simulation Iteration {i} seed: {seed}");
+
+        let mut metrics = IterationMetrics::default();
+        let result = iteration(&simulation_config, &mut metrics, i, seed, timeseries.as_deref_mut());
+
+        // Business metrics are reported for every iteration, however it ended.
+        info!("Simulation Iteration {i} metrics: {metrics:?}");
+        oversold_units += metrics.oversold_units;
+        lost_updates += metrics.lost_updates;
+        excluded_intervals += metrics.excluded_intervals;
+        soundness_violations += metrics.soundness_violations;
+        lost_sales += metrics.lost_sales;
+        for (name, time) in metrics.wrong_display_time {
+            *wrong_display_time.entry(name).or_insert(Duration::ZERO) += time;
+        }
+
+        match result {
             Ok(x) => {
                 success += 1;
                 info!("Simulation Iteration {i} ended with Success!");
-                if let Some(convergence_time) = x {
-                    convergence_times.push(convergence_time);
+                if !x.is_empty() {
+                    convergence_times.push(x.iter().sum::<Duration>() / x.len() as u32);
                 }
+                convergence_events.extend(x);
             },
             Err(e) => match e {
                 SimulationError::Divergence(error) => {
@@ -121,6 +269,11 @@ pub fn driver(
                     info!("Reason: {conflict:#?}");
                     conflicts.push(conflict);
                 }
+                SimulationError::UnexpectedOutcome(outcome) => {
+                    error!("Simulation Iteration {i} ended with an unexpected outcome!");
+                    error!("Expected {}, interpreted {:?}", outcome.expected, outcome.observed);
+                    unexpected_outcomes += 1;
+                }
                 SimulationError::Other(x) => {
                     error!("Simulation Iteration {i} failed with error:");
                     error!("{x:#?}");
@@ -128,6 +281,18 @@ pub fn driver(
                 }
             }
         }
+        completed += 1;
+
+        if let Some(rule) = stopping {
+            if rule.satisfied(completed, conflicts.len() as u64, divergence.len() as u64, &convergence_times) {
+                info!("Stopping after {completed} iterations- confidence intervals are within target");
+                break;
+            }
+        }
+    }
+
+    if let Some(timeseries) = timeseries {
+        timeseries.flush();
     }
 
     SimulationResults {
@@ -144,9 +309,21 @@ pub fn driver(
                 None
             } else {
                 Some(divergence.iter().map(|d| d.diverged_at.duration_since(MonotonicTime::EPOCH)).sum::<Duration>()/ divergence.len() as u32)
-            }
+            },
+            oversold_units,
+            lost_sales,
+            lost_updates,
+            wrong_display_time,
+            unexpected_outcomes,
+            excluded_intervals,
+            soundness_violations,
+            conflict_rate_interval: wilson(conflicts.len() as u64, completed, Z_95),
+            divergence_rate_interval: wilson(divergence.len() as u64, completed, Z_95),
+            mean_convergence_interval: mean_interval(&convergence_times, Z_95),
         },
-        iterations,
+        iterations: completed,
+        convergence_times,
+        convergence_events,
         conflicts,
         divergence,
     }
@@ -4,11 +4,13 @@ use tai_time::MonotonicTime;
 use crate::interpreter::error::ConflictError;
 use crate::interpreter::history::History;
 use crate::simulation::driver::TruthRecord;
+use crate::value::Value;
 
 #[derive(Debug)]
 pub enum SimulationError {
     Divergence(DivergenceError),
     Conflict(ConflictError<MonotonicTime>),
+    UnexpectedOutcome(UnexpectedOutcomeError),
     Other(Box<dyn Error>),
 }
 
@@ -22,4 +24,11 @@ pub struct DivergenceError {
     pub(crate) diverged_at: MonotonicTime,
     pub(crate) truth: Vec<TruthRecord>, // Whole true sequence of events.
     pub(crate) history: History<MonotonicTime> // Whole history for divergence.
+}
+
+// A scripted scenario ended with an interpreted value other than the one expected.
+#[derive(Debug)]
+pub struct UnexpectedOutcomeError {
+    pub(crate) expected: Value,
+    pub(crate) observed: Option<Value>, // None if the interpreter never produced a value.
 }
\ No newline at end of file
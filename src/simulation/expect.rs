@@ -0,0 +1,126 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::simulation::results::SimulationResults;
+use crate::validation::{issue, Validate, ValidationIssue};
+
+// Expected results of a scenario- any bound which is given must hold for `synchro check` to pass.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExpectConfig {
+    #[serde(default)]
+    pub(crate) max_conflict_rate: Option<f64>, // Fraction of iterations ending in conflict.
+    #[serde(default)]
+    pub(crate) max_divergence_rate: Option<f64>, // Fraction of iterations ending diverged.
+    #[serde(default)]
+    pub(crate) max_conflicts: Option<u64>,
+    #[serde(default)]
+    pub(crate) max_divergences: Option<u64>, // 0 for scenarios which must never diverge.
+    #[serde(default)]
+    pub(crate) max_p95_convergence: Option<Duration>, // Over every convergence, pooled across iterations.
+    #[serde(default)]
+    pub(crate) max_mean_convergence: Option<Duration>, // Over the mean of each iteration which diverged then converged.
+    #[serde(default)]
+    pub(crate) max_oversold_units: Option<u64>,
+    #[serde(default)]
+    pub(crate) max_lost_updates: Option<u64>,
+    #[serde(default)]
+    pub(crate) max_lost_sales: Option<u64>,
+    #[serde(default)]
+    pub(crate) max_soundness_violations: Option<u64>, // Needs soundness mode.
+}
+
+impl ExpectConfig {
+    // Every violated expectation, described.
+    pub fn check(&self, results: &SimulationResults) -> Vec<String> {
+        let statistics = &results.statistics;
+        let iterations = results.iterations.max(1) as f64;
+        let mut violations = vec![];
+
+        if let Some(max) = self.max_conflict_rate {
+            let rate = statistics.conflict_number as f64 / iterations;
+            if rate > max {
+                violations.push(format!("Conflict rate {rate:.4} exceeds {max}"));
+            }
+        }
+        if let Some(max) = self.max_divergence_rate {
+            let rate = statistics.divergence_number as f64 / iterations;
+            if rate > max {
+                violations.push(format!("Divergence rate {rate:.4} exceeds {max}"));
+            }
+        }
+        if let Some(max) = self.max_conflicts {
+            if statistics.conflict_number > max {
+                violations.push(format!("{} conflicts exceeds {max}", statistics.conflict_number));
+            }
+        }
+        if let Some(max) = self.max_divergences {
+            if statistics.divergence_number > max {
+                violations.push(format!("{} divergences exceeds {max}", statistics.divergence_number));
+            }
+        }
+        if let Some(max) = self.max_p95_convergence {
+            if let Some(p95) = percentile(&results.convergence_events, 0.95) {
+                if p95 > max {
+                    violations.push(format!("p95 convergence time {p95:.3?} exceeds {max:.3?}"));
+                }
+            }
+        }
+        if let Some(max) = self.max_mean_convergence {
+            if !results.convergence_times.is_empty() {
+                let mean = results.convergence_times.iter().sum::<Duration>() / results.convergence_times.len() as u32;
+                if mean > max {
+                    violations.push(format!("Mean convergence time {mean:.3?} exceeds {max:.3?}"));
+                }
+            }
+        }
+        if let Some(max) = self.max_oversold_units {
+            if statistics.oversold_units > max {
+                violations.push(format!("{} oversold units exceeds {max}", statistics.oversold_units));
+            }
+        }
+        if let Some(max) = self.max_lost_updates {
+            if statistics.lost_updates > max {
+                violations.push(format!("{} lost updates exceeds {max}", statistics.lost_updates));
+            }
+        }
+        if let Some(max) = self.max_lost_sales {
+            if statistics.lost_sales > max {
+                violations.push(format!("{} lost sales exceeds {max}", statistics.lost_sales));
+            }
+        }
+
+        if let Some(max) = self.max_soundness_violations {
+            if statistics.soundness_violations > max {
+                violations.push(format!("{} soundness violations exceeds {max}", statistics.soundness_violations));
+            }
+        }
+
+        // A scripted expected value must always be met.
+        if statistics.unexpected_outcomes > 0 {
+            violations.push(format!("{} iterations ended on an unexpected value", statistics.unexpected_outcomes));
+        }
+        violations
+    }
+}
+
+// Nearest-rank percentile.
+pub fn percentile(samples: &[Duration], p: f64) -> Option<Duration> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort();
+    let rank = ((p * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+    Some(sorted[rank - 1])
+}
+
+impl Validate for ExpectConfig {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        for (field, rate) in [("max_conflict_rate", self.max_conflict_rate), ("max_divergence_rate", self.max_divergence_rate)] {
+            if let Some(rate) = rate {
+                if !(0.0..=1.0).contains(&rate) {
+                    issue(issues, format!("{path}.{field}"), format!("Rate must be between 0 and 1, got {rate}"));
+                }
+            }
+        }
+    }
+}
@@ -1,60 +1,172 @@
-use std::collections::LinkedList;
-use nexosim::model::{Context, Model};
+use std::sync::Arc;
+use std::time::Duration;
+use log::info;
+use nexosim::model::{Context, InitializedModel, Model};
 use nexosim::ports::{Output};
+use serde::{Deserialize, Serialize};
 use tai_time::MonotonicTime;
 use crate::interpreter::error::ConflictError;
 use crate::interpreter::history::History;
+use crate::interpreter::registry::PlatformRegistry;
 use crate::observations::Observation;
+use crate::simulation::network::network_delay::NetworkParameters;
+use crate::validation::{issue, Validate, ValidationIssue};
 use crate::value::Value;
 
+const BATCH_DELAY: Duration = Duration::from_nanos(1); // Smallest step after the batch's instant.
+
 pub struct InterpreterConfig {
-    pub(crate) initial_value: Value
+    pub(crate) initial_value: Value,
+    pub(crate) params: InterpreterParameters,
+    pub(crate) registry: Arc<PlatformRegistry>,
+}
+
+// Deployment of the interpreter as a separate service. Defaults to a co-located, instant interpreter that never fails.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InterpreterParameters {
+    #[serde(default)]
+    pub(crate) network_params: Option<NetworkParameters>, // Link between each interface and the interpreter.
+    #[serde(default)]
+    pub(crate) processing_time: Duration, // Per batch- observations arriving meanwhile join the batch.
+    #[serde(default)]
+    pub(crate) crashes: Vec<InterpreterCrash>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InterpreterCrash {
+    pub(crate) at: Duration, // Since simulation start.
+    pub(crate) downtime: Duration, // Observations sent meanwhile are lost.
+    pub(crate) restart: RestartMode,
+}
+
+// What the interpreter has on restart.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum RestartMode {
+    Cold, // Nothing- starts again from the initial value.
+    StableValue, // Only its persisted stable value- history is lost.
+    Full, // Both history and stable value are persisted.
+}
+
+impl Validate for InterpreterParameters {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        if let Some(network_params) = &self.network_params {
+            network_params.validate(&format!("{path}.network_params"), issues);
+        }
+        for (i, crash) in self.crashes.iter().enumerate() {
+            if crash.at.is_zero() {
+                issue(issues, format!("{path}.crashes[{i}].at"), "Crash must happen after the simulation starts");
+            }
+            if crash.downtime.is_zero() {
+                issue(issues, format!("{path}.crashes[{i}].downtime"), "Downtime must be greater than zero");
+            }
+            if let Some(previous) = i.checked_sub(1).map(|j| &self.crashes[j]) {
+                if crash.at < previous.at + previous.downtime {
+                    issue(issues, format!("{path}.crashes[{i}].at"), "Crash happens before the previous restart");
+                }
+            }
+        }
+    }
 }
 
 pub struct Interpreter {
     history: History<MonotonicTime>,
     config: InterpreterConfig,
     stable_value: Option<Value>,
+    pending: Vec<Observation<MonotonicTime>>, // Observations of the current batch, not yet applied.
+    batch: u64, // Generation of the current batch- flushes scheduled for batches lost in a crash are ignored.
+    down: bool, // Crashed, and not yet restarted.
     pub(crate) found_out: Output<Result<Value, ConflictError<MonotonicTime>>>
 }
 
 impl Interpreter {
     pub fn new(config: InterpreterConfig) -> Self {
         Interpreter {
-            history: History::new(),
+            history: History::with_registry(config.registry.clone()),
             stable_value: Some(config.initial_value),
+            pending: Vec::new(),
+            batch: 0,
+            down: false,
             found_out: Default::default(),
             config
         }
     }
 
-    // TODO: Requestor/buffered port here to improve simulation speed.
-    pub (crate) async fn input(&mut self, observation: Observation<MonotonicTime>, ctx: &mut Context<Self>) {
+    pub (crate) fn input(&mut self, observation: Observation<MonotonicTime>, ctx: &mut Context<Self>) {
         // debug!("Observed {:?} at {}", observation, ctx.time());
 
-        // info!("Had: {:?}", self.stable_value);
-        // info!("Got: {observation:?}");
+        if self.down {
+            // info!("Lost: {observation:?}");
+            return;
+        }
 
-        let mut pruned = History {
-            list: LinkedList::from_iter(self.history.insert(observation, ctx.time()).into_iter()),
-        };
+        // Buffer observations arriving at the same instant (or while processing)- processed as one batch (as the real-world interpreter does).
+        // Events must be scheduled strictly in the future, so the batch is flushed at least 1ns later.
+        if self.pending.is_empty() {
+            ctx.schedule_event(ctx.time() + self.config.params.processing_time.max(BATCH_DELAY), Self::flush, self.batch).unwrap();
+        }
+        self.pending.push(observation);
+    }
 
+    pub (crate) async fn flush(&mut self, batch: u64, ctx: &mut Context<Self>) {
+        if self.down || batch != self.batch || self.pending.is_empty() {
+            return; // Batch was lost in a crash- a later batch has its own flush.
+        }
+        self.batch += 1;
 
-        // info!("Pruning: {pruned:?}");
-        self.stable_value = match pruned.apply(self.stable_value, ctx.time()) {
-            Ok(value) => Some(value),
-            Err(_) => {
-                None
-            }
-        };
+        // info!("Had: {:?}", self.stable_value);
+        for observation in std::mem::take(&mut self.pending) {
+            // info!("Got: {observation:?}");
+            let pruned_regions = self.history.insert(observation, ctx.time());
+            let mut pruned = self.history.pruned(pruned_regions);
+
+            // info!("Pruning: {pruned:?}");
+            self.stable_value = match pruned.apply(self.stable_value, ctx.time()) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    None
+                }
+            };
+        }
 
         // info!("Now Have: {:?}", self.stable_value);
+        // One recomputation and one broadcast per batch.
         let result = self.history.apply(self.stable_value, ctx.time());
         // info!("Sending: {:?}", result);
         // Send the result of history applied to initial value.
         self.found_out.send(result).await;
     }
+
+    pub (crate) fn crash(&mut self, crash: InterpreterCrash, ctx: &mut Context<Self>) {
+        info!("Interpreter crashed at {:?}, restarting {:?} after {:?}", ctx.time(), crash.restart, crash.downtime);
+        self.down = true;
+        self.pending.clear(); // Batch in progress is lost.
+        self.batch += 1;
+        ctx.schedule_event(ctx.time() + crash.downtime, Self::restart, crash.restart).unwrap();
+    }
+
+    pub (crate) fn restart(&mut self, mode: RestartMode, ctx: &mut Context<Self>) {
+        info!("Interpreter restarted at {:?} with {:?}", ctx.time(), mode);
+        match mode {
+            RestartMode::Cold => {
+                self.history = History::with_registry(self.config.registry.clone());
+                self.stable_value = Some(self.config.initial_value);
+            }
+            RestartMode::StableValue => self.history = History::with_registry(self.config.registry.clone()),
+            RestartMode::Full => (),
+        }
+        self.down = false;
+    }
 }
 
 
-impl Model for Interpreter {}
\ No newline at end of file
+impl Model for Interpreter {
+    async fn init(self, ctx: &mut Context<Self>) -> InitializedModel<Self> {
+        // Schedule all crashes up front.
+        for crash in &self.config.params.crashes {
+            let at = ctx.time() + crash.at;
+            ctx.schedule_event(at, Self::crash, crash.clone()).expect(format!("Error Scheduling At: {:?}", at).as_str());
+        }
+
+        self.into()
+    }
+}
\ No newline at end of file
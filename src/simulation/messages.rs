@@ -1,6 +1,7 @@
 use crate::simulation::polling::r#unsafe::messages::UnsafePollQuery;
 use crate::simulation::polling::safe::messages::SafePollQuery;
 use crate::simulation::record::messages::RecordQuery;
+use serde::{Deserialize, Serialize};
 use crate::value::Value;
 
 #[derive(Debug, Clone)]
@@ -9,7 +10,7 @@ pub enum PlatformQuery {
     Interface(InterfaceQuery)
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum UserAction {
     Mutation(Value),
     Assignment(Value)
@@ -10,4 +10,11 @@ pub mod error;
 pub mod results;
 pub mod driver;
 pub mod model;
+pub mod timeseries;
+pub mod script;
+pub mod expect;
+pub mod statistics;
+pub mod compare;
+pub mod clock;
+pub mod soundness;
 pub mod record;
@@ -1,76 +1,145 @@
+use std::collections::HashMap;
+use std::sync::Arc;
 use nexosim::ports::{EventBuffer, EventSlot};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use nexosim::simulation::{Mailbox, SimInit, Simulation};
 use tai_time::MonotonicTime;
 use crate::interpreter::error::ConflictError;
+use crate::observations::Observation;
 use crate::simulation::config::{PlatformConfig, SimulationConfig};
-use crate::simulation::driver::TruthRecord;
+use crate::simulation::driver::{DisplayRecord, LostUpdateRecord, ObservationRecord, RefusedSaleRecord, TruthRecord};
 use crate::simulation::interpreter::interpreter::{Interpreter, InterpreterConfig};
+use crate::simulation::network::network_delay::NetworkConnection;
 use crate::simulation::polling::r#unsafe::{ProtoUnsafePollingModel, UnsafePollingModel};
 use crate::simulation::polling::safe::{ProtoSafePollingModel, SafePollingModel};
 use crate::simulation::record::ProtoRecordModel;
 use crate::value::Value;
 
+// Network between an interface and the interpreter- observations one way, interpreted values the other.
+type InterpreterLink = NetworkConnection<Observation<MonotonicTime>, Result<Value, ConflictError<MonotonicTime>>>;
+
 pub fn build_model(
     cfg: &SimulationConfig,
+    seed: u64, // Seeds every random model, so that an iteration can be reproduced.
     truth_sink: &EventBuffer<TruthRecord>,
+    display_sink: &EventBuffer<DisplayRecord>,
+    lost_update_sink: &EventBuffer<LostUpdateRecord>,
+    refused_sale_sink: &EventBuffer<RefusedSaleRecord>,
+    observation_sink: &EventBuffer<ObservationRecord>,
     found_slot: &EventSlot<Result<Value, ConflictError<MonotonicTime>>>
 ) -> Simulation {
     let t0 = MonotonicTime::EPOCH; // Start at EPOCH!
     let mut model = SimInit::new();
 
     let mut interpreter = Interpreter::new(InterpreterConfig {
-        initial_value: cfg.initial_value
+        initial_value: cfg.initial_value,
+        params: cfg.interpreter.clone(),
+        registry: Arc::new(cfg.registry.clone()),
     });
     let interpreter_in = Mailbox::new();
     interpreter.found_out.connect_sink(found_slot);
 
 
-    for (name, polling_cfg) in cfg.platforms.iter() {
+    // Platforms in name order, so that each gets the same seed (and is added in the same order) every run.
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut platforms: Vec<_> = cfg.platforms.iter().collect();
+    platforms.sort_by_key(|(name, _)| *name);
+
+    for (name, polling_cfg) in platforms {
+        let platform_seed = rng.random();
+        let link_seed = rng.random();
+
+        // Interpreter deployed as a separate service is reached over its own link.
+        let mut link = cfg.interpreter.network_params.clone()
+            .map(|params| InterpreterLink::new(params, HashMap::new(), StdRng::seed_from_u64(link_seed)));
+        let link_mbox = Mailbox::new();
+
         match polling_cfg {
             PlatformConfig::PollingSafe(safe_cfg) => {
-                let mut polling_model = ProtoSafePollingModel::new(name.clone(), safe_cfg.clone());
+                let mut polling_model = ProtoSafePollingModel::new(name.clone(), safe_cfg.clone(), cfg.script.for_platform(name), cfg.allocation.policy(name), platform_seed);
                 let polling_mbox = Mailbox::new();
 
                 // Attach truth output.
                 polling_model.truth_output.connect_sink(truth_sink);
 
-                // Attach interface to interpreter.
-                polling_model.observation_output.connect(Interpreter::input, &interpreter_in);
-
-                // Attach interpreter to interface
-                interpreter.found_out.connect(SafePollingModel::write_input, &polling_mbox);
+                // Attach observation output, to check intervals against the truth.
+                polling_model.observation_output.connect_sink(observation_sink);
+
+                // Attach display and refused sale outputs.
+                polling_model.display_output.connect_sink(display_sink);
+                polling_model.refused_sale_output.connect_sink(refused_sale_sink);
+
+                // Attach interface to interpreter, and interpreter to interface.
+                match link.as_mut() {
+                    Some(link) => {
+                        polling_model.observation_output.connect(InterpreterLink::input_1, &link_mbox);
+                        link.output_2.connect(SafePollingModel::write_input, &polling_mbox);
+                    }
+                    None => {
+                        polling_model.observation_output.connect(Interpreter::input, &interpreter_in);
+                        interpreter.found_out.connect(SafePollingModel::write_input, &polling_mbox);
+                    }
+                }
 
                 model = model.add_model(polling_model, polling_mbox, format!("SafePolling-{}", name))
             }
             PlatformConfig::PollingUnsafe(unsafe_cfg) => {
-                let mut polling_model = ProtoUnsafePollingModel::new(name.clone(), unsafe_cfg.clone());
+                let mut polling_model = ProtoUnsafePollingModel::new(name.clone(), unsafe_cfg.clone(), cfg.script.for_platform(name), cfg.allocation.policy(name), platform_seed);
                 let polling_mbox = Mailbox::new();
 
                 // Attach truth output.
                 polling_model.truth_output.connect_sink(truth_sink);
 
-                // Attach interface to interpreter
-                polling_model.observation_output.connect(Interpreter::input, &interpreter_in);
-
-                // Attach interpreter to interface
-                interpreter.found_out.connect(UnsafePollingModel::write_input, &polling_mbox);
+                // Attach observation output, to check intervals against the truth.
+                polling_model.observation_output.connect_sink(observation_sink);
+
+                // Attach display, lost update and refused sale outputs.
+                polling_model.display_output.connect_sink(display_sink);
+                polling_model.lost_update_output.connect_sink(lost_update_sink);
+                polling_model.refused_sale_output.connect_sink(refused_sale_sink);
+
+                // Attach interface to interpreter, and interpreter to interface.
+                match link.as_mut() {
+                    Some(link) => {
+                        polling_model.observation_output.connect(InterpreterLink::input_1, &link_mbox);
+                        link.output_2.connect(UnsafePollingModel::write_input, &polling_mbox);
+                    }
+                    None => {
+                        polling_model.observation_output.connect(Interpreter::input, &interpreter_in);
+                        interpreter.found_out.connect(UnsafePollingModel::write_input, &polling_mbox);
+                    }
+                }
                 model = model.add_model(polling_model, polling_mbox, format!("UnsafePolling-{}", name))
             }
             PlatformConfig::Record(record_cfg) => {
-                let mut record_model = ProtoRecordModel::new(name.clone(), record_cfg.clone());
+                let mut record_model = ProtoRecordModel::new(name.clone(), record_cfg.clone(), cfg.script.for_platform(name), platform_seed);
                 let record_mbox = Mailbox::new();
 
                 // Attach truth output.
                 record_model.truth_output.connect_sink(truth_sink);
 
+                // Attach observation output, to check intervals against the truth.
+                record_model.observation_output.connect_sink(observation_sink);
+
                 // Attach interface to interpreter
-                record_model.observation_output.connect(Interpreter::input, &interpreter_in);
+                if link.is_some() {
+                    record_model.observation_output.connect(InterpreterLink::input_1, &link_mbox);
+                } else {
+                    record_model.observation_output.connect(Interpreter::input, &interpreter_in);
+                }
 
                 // We do not model writes for record- as they do not effect visibility.
                 model = model.add_model(record_model, record_mbox, format!("Record-{}", name))
             }
             _ => unreachable!()
         }
+
+        if let Some(mut link) = link {
+            link.output_1.connect(Interpreter::input, &interpreter_in);
+            interpreter.found_out.connect(InterpreterLink::input_2, &link_mbox);
+            model = model.add_model(link, link_mbox, format!("InterpreterLink-{}", name));
+        }
     }
 
     model = model.add_model(interpreter, interpreter_in, "Interpreter");
@@ -1,9 +1,12 @@
+use std::collections::HashMap;
 use std::time::Duration;
 use nexosim::model::{Context, Model};
 use nexosim::ports::{Output};
 use rand::Rng;
+use rand::rngs::StdRng;
 use rand_distr::Pareto;
 use serde::{Deserialize, Serialize};
+use crate::validation::{issue, Validate, ValidationIssue};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NetworkParameters {
@@ -11,25 +14,52 @@ pub struct NetworkParameters {
     pub(crate) scale: f64 // Scale of Pareto - Larger = less extremes.
 }
 
+impl Validate for NetworkParameters {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        // Pareto requires both to be positive and finite.
+        if !(self.size.is_finite() && self.size > 0.0) {
+            issue(issues, format!("{path}.size"), format!("Network latency must be positive, got {}", self.size));
+        }
+        if !(self.scale.is_finite() && self.scale > 0.0) {
+            issue(issues, format!("{path}.scale"), format!("Network stability must be positive, got {}", self.scale));
+        }
+    }
+}
+
+// Direction of a message through a connection- used to pin delays of specific messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NetworkDirection {
+    ToInterface, // Platform replies (input_1 -> output_1).
+    ToPlatform, // Interface queries (input_2 -> output_2).
+}
 
 pub struct NetworkConnection<MessageType1: Clone + Send + Sync + 'static, MessageType2:  Clone + Send + Sync + 'static> {
     pub output_1: Output<MessageType1>,
     pub output_2: Output<MessageType2>,
     distribution: Pareto<f64>,
+    rng: StdRng, // Seeded per iteration, so that runs can be reproduced.
+    pinned: HashMap<(NetworkDirection, u64), Duration>, // Scripted delays, by direction and message index.
+    sent_to_interface: u64, // Messages seen in each direction.
+    sent_to_platform: u64,
 }
 impl<MessageType1: Clone + Send + Sync + 'static, MessageType2: Clone + Send + Sync + 'static> NetworkConnection<MessageType1, MessageType2> {
-    pub fn new(network_parameters: NetworkParameters) -> Self {
+    pub fn new(network_parameters: NetworkParameters, pinned: HashMap<(NetworkDirection, u64), Duration>, rng: StdRng) -> Self {
         NetworkConnection {
             output_1: Output::default(),
             output_2: Output::default(),
             // Pareto Distribution- as per: http://blog.simiacryptus.com/posts/modeling_network_latency/
-            distribution: Pareto::new(network_parameters.size, network_parameters.scale).unwrap()
+            distribution: Pareto::new(network_parameters.size, network_parameters.scale).unwrap(),
+            rng,
+            pinned,
+            sent_to_interface: 0,
+            sent_to_platform: 0,
         }
     }
 
     pub fn input_1(&mut self, value: MessageType1, ctx: &mut Context<Self>) {
         // When get input- schedule output for after network delay.
-        ctx.schedule_event(self.delay(), Self::send_1, value).unwrap();
+        let delay = self.pinned_delay(NetworkDirection::ToInterface).unwrap_or_else(|| self.delay());
+        ctx.schedule_event(delay, Self::send_1, value).unwrap();
     }
 
     pub async fn send_1(&mut self, value: MessageType1) {
@@ -42,11 +72,23 @@ impl<MessageType1: Clone + Send + Sync + 'static, MessageType2: Clone + Send + S
 
     pub fn input_2(&mut self, value: MessageType2, ctx: &mut Context<Self>) {
         // When get input- schedule output for after network delay.
-        ctx.schedule_event(self.delay(), Self::send_2, value).unwrap();
+        let delay = self.pinned_delay(NetworkDirection::ToPlatform).unwrap_or_else(|| self.delay());
+        ctx.schedule_event(delay, Self::send_2, value).unwrap();
+    }
+
+    // Count the message, and return its scripted delay if it has one.
+    fn pinned_delay(&mut self, direction: NetworkDirection) -> Option<Duration> {
+        let counter = match direction {
+            NetworkDirection::ToInterface => &mut self.sent_to_interface,
+            NetworkDirection::ToPlatform => &mut self.sent_to_platform,
+        };
+        let index = *counter;
+        *counter += 1;
+        self.pinned.get(&(direction, index)).copied()
     }
 
     pub fn delay(&mut self) -> Duration {
-        let net_delay = Duration::from_millis(rand::rng().sample(self.distribution).round() as u64);
+        let net_delay = Duration::from_millis(self.rng.sample(self.distribution).round() as u64);
         // println!("Delay: {net_delay:?}");
         net_delay
     }
@@ -1,5 +1,7 @@
 use std::time::Duration;
 use serde::{Deserialize, Serialize};
+use crate::simulation::clock::ClockParameters;
+use crate::validation::{issue, Validate, ValidationIssue};
 
 // Common interface params for Unsafe and Safe
 
@@ -8,6 +10,18 @@ use serde::{Deserialize, Serialize};
 pub struct PollingInterfaceParameters {
     pub(crate) interp: PollingInterpretation,
     pub(crate) backoff: Duration,
+    #[serde(default)]
+    pub(crate) clock: ClockParameters, // Skew of the interface host's clock, which stamps intervals.
+}
+
+impl Validate for PollingInterfaceParameters {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        // Polls are scheduled after backoff- which must be in the future.
+        if self.backoff.is_zero() {
+            issue(issues, format!("{path}.backoff"), "Poll backoff must be greater than zero");
+        }
+        self.clock.validate(&format!("{path}.clock"), issues);
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
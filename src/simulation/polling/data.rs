@@ -1,4 +1,5 @@
 use tai_time::MonotonicTime;
+use crate::allocation::{allocated, ChannelAllocation};
 use crate::value::Value;
 // Common datastructures between safe and unsafe polling.
 
@@ -11,17 +12,28 @@ pub struct SentPoll {
 pub struct FinishedPoll {
     pub(crate) sent: MonotonicTime,
     pub(crate) value: Value,
+    pub(crate) truth_seen: u64, // Number of the platform's true events reflected in value.
 }
 
 #[derive(Debug)]
 pub struct PollState {
     pub(crate) ordering: u64, // Monotonic logical ordering.
     pub(crate) current: Option<SentPoll>,
-    pub(crate) last: FinishedPoll
+    pub(crate) last: FinishedPoll,
+    pub(crate) offset: Value, // Stock minus the channel's value, as of the last write- translates polled values back to stock.
 }
 
 #[derive(Debug)]
 pub struct WriteState {
     pub(crate) sent: MonotonicTime,
-    pub(crate) value: Value,
+    pub(crate) value: Value, // As written to the channel.
+    pub(crate) offset: Value, // Stock minus value.
+}
+
+impl WriteState {
+    // Write of the interpreted stock, as allocated to the channel.
+    pub fn allocated(sent: MonotonicTime, stock: Value, allocation: Option<&ChannelAllocation>) -> WriteState {
+        let value = allocated(allocation, stock);
+        WriteState { sent, value, offset: stock - value }
+    }
 }
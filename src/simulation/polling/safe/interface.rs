@@ -3,6 +3,7 @@ use nexosim::model::{Context, InitializedModel, Model};
 use nexosim::ports::Output;
 use nexosim::simulation::ActionKey;
 use nexosim::time::MonotonicTime;
+use crate::allocation::ChannelAllocation;
 use crate::interpreter::error::ConflictError;
 use crate::intervals::Interval;
 use crate::observations::{Observation};
@@ -19,6 +20,7 @@ use crate::value::Value;
 pub struct SafePollingInterface {
     name: String,
     config: PollingInterfaceParameters,
+    allocation: Option<ChannelAllocation>, // Between the interpreted stock and the value written.
     poll_state: PollState,
     write_state: Option<WriteState>,
     waiting_write: Option<Value>,
@@ -28,17 +30,22 @@ pub struct SafePollingInterface {
 }
 
 impl SafePollingInterface {
-    pub fn new(name: String, config: PollingInterfaceParameters, initial_value: Value) -> SafePollingInterface {
+    pub fn new(name: String, config: PollingInterfaceParameters, initial_value: Value, allocation: Option<ChannelAllocation>) -> SafePollingInterface {
+        // Platform starts showing the initial value as allocated.
+        let initial = WriteState::allocated(MonotonicTime::EPOCH, initial_value, allocation.as_ref());
         SafePollingInterface {
             name,
             config,
+            allocation,
             poll_state: PollState {
                 ordering: 0,
                 current: None,
                 last: FinishedPoll {
                     sent: MonotonicTime::EPOCH, // Initial value is from 0.
-                    value: initial_value
-                }
+                    value: initial.value,
+                    truth_seen: 0,
+                },
+                offset: initial.offset,
             },
             write_state: None,
             waiting_write: None,
@@ -61,14 +68,12 @@ impl SafePollingInterface {
                     // And send the write now.
                     // If no write has been sent- clear any waiting and send.
                     self.waiting_write = None;
+                    let write = WriteState::allocated(self.config.clock.now(ctx.time()), value, self.allocation.as_ref());
                     // Guard with last observed value.
-                    self.query_output.send(PlatformQuery::Interface(InterfaceQuery::PollingSafe(SafePollQuery::Write(value, self.poll_state.last.value)))).await;
+                    self.query_output.send(PlatformQuery::Interface(InterfaceQuery::PollingSafe(SafePollQuery::Write(write.value, self.poll_state.last.value)))).await;
                     // Log write.
                     // info!("TRACE3 - WRITE STATE SET TO {}", ctx.time());
-                    self.write_state = Some(WriteState {
-                        sent: ctx.time(),
-                        value
-                    });
+                    self.write_state = Some(write);
                 // If there is no next poll, either a different write has been sent or poll is inflight.
                 // Therefore, wait to send write until replies.
                 } else {
@@ -84,13 +89,13 @@ impl SafePollingInterface {
         // info!("{} got {reply:?} at {:?}", self.name, ctx.time());
         match reply {
             // If poll query replies, and does not equal last.
-            SafePollReply::Query(v) => {
+            SafePollReply::Query(v, truth_seen) => {
                 let current_poll = self.poll_state.current.take().unwrap();
 
                 // info!{"Current Poll: {}", current_poll.at}
                 if v != self.poll_state.last.value {
                     // info!("Generating Observation at {:?}", ctx.time());
-                    self.generate_observation(self.poll_state.last.value, v, ctx).await;
+                    self.generate_observation(self.poll_state.last.value, v, truth_seen, ctx).await;
                 }
 
 
@@ -99,20 +104,19 @@ impl SafePollingInterface {
                 self.poll_state.last = FinishedPoll {
                     sent: current_poll.at,
                     value: v,
+                    truth_seen,
                 };
 
                 // If a write is waiting- send it.
                 if let Some(waiting) = self.waiting_write {
                     // debug!("Write waiting on inflight poll- being sent now!");
+                    let write = WriteState::allocated(self.config.clock.now(ctx.time()), waiting, self.allocation.as_ref());
                     // Send it, guarded with new observed value.
-                    self.query_output.send(PlatformQuery::Interface(InterfaceQuery::PollingSafe(SafePollQuery::Write(waiting, v)))).await;
+                    self.query_output.send(PlatformQuery::Interface(InterfaceQuery::PollingSafe(SafePollQuery::Write(write.value, v)))).await;
 
                     // Log write.
                     // info!("TRACE2 - WRITE STATE SET TO {}", ctx.time());
-                    self.write_state = Some(WriteState {
-                        sent: ctx.time(),
-                        value: waiting,
-                    });
+                    self.write_state = Some(write);
 
                     // Clear waiting.
                     self.waiting_write = None;
@@ -132,20 +136,20 @@ impl SafePollingInterface {
                 self.poll_state.last = FinishedPoll {
                     value: successful_write.value,
                     sent: successful_write.sent,
+                    truth_seen: self.poll_state.last.truth_seen, // No true events since.
                 };
+                self.poll_state.offset = successful_write.offset;
 
                 // If there's another write waiting...
                 if let Some(waiting) = self.waiting_write {
                     // debug!("Write waiting on inflight write- being sent now!");
+                    let write = WriteState::allocated(self.config.clock.now(ctx.time()), waiting, self.allocation.as_ref());
                     // Send it, guarded with new successfully written value.
-                    self.query_output.send(PlatformQuery::Interface(InterfaceQuery::PollingSafe(SafePollQuery::Write(waiting, successful_write.value)))).await;
+                    self.query_output.send(PlatformQuery::Interface(InterfaceQuery::PollingSafe(SafePollQuery::Write(write.value, successful_write.value)))).await;
 
                     // info!("TRACE1 - WRITE STATE SET TO {}", ctx.time());
                     // Log write.
-                    self.write_state = Some(WriteState {
-                        sent: ctx.time(),
-                        value: waiting,
-                    });
+                    self.write_state = Some(write);
 
                     // Clear waiting.
                     self.waiting_write = None;
@@ -158,13 +162,13 @@ impl SafePollingInterface {
                 }
             }
             // If write failed...
-            SafePollReply::WriteFail(new_value) => {
+            SafePollReply::WriteFail(new_value, truth_seen) => {
 
                 // debug!("Write failed- voiding pending write and making observation!");
                 let failed_write = self.write_state.take().unwrap();
                 // Is due to a change-
                 // info!("WRITE FAILED: {:?}, {:?}, {:?}, {:?}, {:?}", failed_write, self.poll_state.last.value, new_value, self.poll_state.last.sent, ctx.time());
-                self.generate_observation(self.poll_state.last.value, new_value, ctx).await;
+                self.generate_observation(self.poll_state.last.value, new_value, truth_seen, ctx).await;
 
                 // If write waiting, clear it as new obs will likely overwrite it.
                 self.waiting_write = None;
@@ -173,6 +177,7 @@ impl SafePollingInterface {
                 self.poll_state.last = FinishedPoll {
                     value: new_value,
                     sent: failed_write.sent,
+                    truth_seen,
                 };
 
                 // Schedule new poll- for the hell of it.
@@ -185,20 +190,22 @@ impl SafePollingInterface {
         }
     }
 
-    pub async fn generate_observation(&mut self, from: Value, to: Value, ctx: &mut Context<Self>) {
-        if ctx.time() < self.poll_state.last.sent {
-            error!("Got Backwards Observation: {:?}, {:?}", self.poll_state.last.sent, ctx.time());
+    pub async fn generate_observation(&mut self, from: Value, to: Value, truth_seen: u64, ctx: &mut Context<Self>) {
+        if self.config.clock.now(ctx.time()) < self.poll_state.last.sent {
+            error!("Got Backwards Observation: {:?}, {:?}", self.poll_state.last.sent, self.config.clock.now(ctx.time()));
         };
         self.observation_output.send(Observation {
-            interval: Interval(self.poll_state.last.sent, ctx.time()),
+            interval: Interval(self.poll_state.last.sent, self.config.clock.now(ctx.time())), // Stamped by the interface's own clock.
             definition_predicate: match self.config.interp {
-                PollingInterpretation::Transition => Transition(from, to),
+                // Channel values are translated back to stock- deltas are the same in both.
+                PollingInterpretation::Transition => Transition(from + self.poll_state.offset, to + self.poll_state.offset),
                 PollingInterpretation::AllMut => AllMut(to - from),
-                PollingInterpretation::LastAssn => LastAssn(to)
+                PollingInterpretation::LastAssn => LastAssn(to + self.poll_state.offset)
             },
             source: self.name.clone(),
             platform_metadata: PlatformMetadata::Simulation(SimulationMetaData {
-                monotonic: self.poll_state.ordering
+                monotonic: self.poll_state.ordering,
+                truth: self.poll_state.last.truth_seen..truth_seen, // True events since the last poll.
             }),
         }).await;
         self.poll_state.ordering += 1;
@@ -211,7 +218,7 @@ impl SafePollingInterface {
         // info!("Wrote Current Poll to {}", ctx.time());
         // Log poll.
         self.poll_state.current = Some(SentPoll {
-            at: ctx.time(),
+            at: self.config.clock.now(ctx.time()),
         });
         // Send query.
         self.query_output.send(PlatformQuery::Interface(InterfaceQuery::PollingSafe(SafePollQuery::Query))).await;
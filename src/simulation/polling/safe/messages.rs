@@ -2,9 +2,9 @@ use crate::value::Value;
 
 #[derive(Debug, Copy, Clone)]
 pub enum SafePollReply {
-    Query(Value), // Query response- value at time of processing.
+    Query(Value, u64), // Query response- value at time of processing, and number of true events so far.
     WriteSuccess, // Write succeeded- no change since supplied value.
-    WriteFail(Value) // Write failed as has changed since supplied value. Return new value.
+    WriteFail(Value, u64) // Write failed as has changed since supplied value. Return new value, and number of true events so far.
 }
 
 #[derive(Debug, Copy, Clone)]
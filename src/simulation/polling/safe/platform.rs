@@ -3,28 +3,36 @@ use nexosim::model::{Context, Model};
 use nexosim::ports::Output;
 use crate::simulation::messages::{InterfaceQuery, PlatformQuery, UserAction};
 use crate::predicates::DefinitionPredicate;
-use crate::simulation::driver::TruthRecord;
+use crate::simulation::driver::{DisplayRecord, RefusedSaleRecord, TruthRecord};
 use crate::simulation::polling::safe::messages::{SafePollQuery, SafePollReply};
 use crate::value::Value;
 
 pub struct SafePollingPlatform {
     name: String,
     current_value: Value,
+    refuse_out_of_stock: bool, // Refuse sales past zero, rather than selling into negative stock.
     pub(crate) reply_output: Output<SafePollReply>,
     pub(crate) truth_output: Output<TruthRecord>,
+    pub(crate) display_output: Output<DisplayRecord>,
+    pub(crate) refused_sale_output: Output<RefusedSaleRecord>,
     safety_version: u64,
-    last_seen: u64
+    last_seen: u64,
+    truth_sequence: u64, // Number of true events so far- tags observations in soundness checks.
 }
 
 impl SafePollingPlatform {
-    pub fn new(name: String, initial_value: Value) -> SafePollingPlatform {
+    pub fn new(name: String, initial_value: Value, refuse_out_of_stock: bool) -> SafePollingPlatform {
         SafePollingPlatform {
             name,
             current_value: initial_value,
+            refuse_out_of_stock,
             reply_output: Default::default(),
             truth_output: Default::default(),
+            display_output: Default::default(),
+            refused_sale_output: Default::default(),
             safety_version: 0,
-            last_seen: 0
+            last_seen: 0,
+            truth_sequence: 0,
         }
     }
 
@@ -36,12 +44,18 @@ impl SafePollingPlatform {
     pub async fn process_query(&mut self, query: PlatformQuery, ctx: &mut Context<SafePollingPlatform>) {
         match query {
             PlatformQuery::User(user_action) => match user_action {
+                // When the channel shows too few units, the sale cannot happen.
+                UserAction::Mutation(delta) if self.refuse_out_of_stock && self.current_value + delta < 0 => {
+                    self.refused_sale_output.send((self.name.clone(), -delta, ctx.time())).await;
+                }
                 // When user triggered a mutation...
                 UserAction::Mutation(delta) => {
                     // info!("{} got {query:?} at {:?}", self.name, ctx.time());
                     self.current_value += delta;
                     // Log to truth out.
-                    self.truth_output.send((DefinitionPredicate::AllMut(delta), ctx.time())).await;
+                    self.truth_output.send((self.name.clone(), DefinitionPredicate::AllMut(delta), ctx.time())).await;
+                    self.truth_sequence += 1;
+                    self.display_output.send((self.name.clone(), self.current_value, ctx.time())).await;
                     self.safety_version += 1;
                 }
                 // When user triggered an assignment...
@@ -49,7 +63,9 @@ impl SafePollingPlatform {
                     // info!("{} got {query:?} at {:?}", self.name, ctx.time());
                     self.current_value = value;
                     // Log to truth out.
-                    self.truth_output.send((DefinitionPredicate::LastAssn(value), ctx.time())).await;
+                    self.truth_output.send((self.name.clone(), DefinitionPredicate::LastAssn(value), ctx.time())).await;
+                    self.truth_sequence += 1;
+                    self.display_output.send((self.name.clone(), self.current_value, ctx.time())).await;
                     self.safety_version += 1;
                 }
             },
@@ -57,12 +73,13 @@ impl SafePollingPlatform {
             PlatformQuery::Interface(InterfaceQuery::PollingSafe(safe_query)) => match safe_query {
                 SafePollQuery::Query => {
                     // When getting a query- reply with current state.
-                    self.reply_output.send(SafePollReply::Query(self.current_value.clone())).await;
+                    self.reply_output.send(SafePollReply::Query(self.current_value.clone(), self.truth_sequence)).await;
                     self.last_seen = self.safety_version;
                 },
                 SafePollQuery::Write(to_write, _) => if self.last_seen == self.safety_version {
                     // info!("{} got {query:?} at {:?}", self.name, ctx.time());
                     self.current_value = to_write;
+                    self.display_output.send((self.name.clone(), self.current_value, ctx.time())).await;
                     // Do write and send success.
                     self.reply_output.send(SafePollReply::WriteSuccess).await;
                     self.safety_version = self.safety_version + 1;
@@ -71,7 +88,7 @@ impl SafePollingPlatform {
                     // info!("Write Refused, Last Saw: {}, Current: {}", self.last_seen, self.safety_version);
                     // info!("{} got {query:?} at {:?}", self.name, ctx.time());
                     // Do write failure and send failure.
-                    self.reply_output.send(SafePollReply::WriteFail(self.current_value.clone())).await;
+                    self.reply_output.send(SafePollReply::WriteFail(self.current_value.clone(), self.truth_sequence)).await;
                     self.last_seen = self.safety_version;
                 }
             },
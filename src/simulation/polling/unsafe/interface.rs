@@ -2,6 +2,7 @@ use nexosim::model::{Context, InitializedModel, Model};
 use nexosim::ports::Output;
 use nexosim::simulation::ActionKey;
 use nexosim::time::MonotonicTime;
+use crate::allocation::ChannelAllocation;
 use crate::interpreter::error::ConflictError;
 use crate::intervals::Interval;
 use crate::observations::{Observation};
@@ -18,6 +19,7 @@ use crate::value::Value;
 pub struct UnsafePollingInterface {
     name: String,
     config: PollingInterfaceParameters,
+    allocation: Option<ChannelAllocation>, // Between the interpreted stock and the value written.
     poll_state: PollState,
     write_state: Option<WriteState>,
     waiting_write: Option<Value>,
@@ -27,17 +29,22 @@ pub struct UnsafePollingInterface {
 }
 
 impl UnsafePollingInterface {
-    pub fn new(name: String, config: PollingInterfaceParameters, initial_value: Value) -> UnsafePollingInterface {
+    pub fn new(name: String, config: PollingInterfaceParameters, initial_value: Value, allocation: Option<ChannelAllocation>) -> UnsafePollingInterface {
+        // Platform starts showing the initial value as allocated.
+        let initial = WriteState::allocated(MonotonicTime::EPOCH, initial_value, allocation.as_ref());
         UnsafePollingInterface {
             name,
             config,
+            allocation,
             poll_state: PollState {
                 ordering: 0,
                 current: None,
                 last: FinishedPoll {
                     sent: MonotonicTime::EPOCH, // Initial value is from 0.
-                    value: initial_value
-                }
+                    value: initial.value,
+                    truth_seen: 0,
+                },
+                offset: initial.offset,
             },
             write_state: None,
             waiting_write: None,
@@ -62,9 +69,9 @@ impl UnsafePollingInterface {
     pub async fn platform_input(&mut self, reply: UnsafePollReply, ctx: &mut Context<Self>) {
         match reply {
             // If poll query replies, and does not equal last.
-            UnsafePollReply::Query(v) => {
+            UnsafePollReply::Query(v, truth_seen) => {
                 if v != self.poll_state.last.value {
-                    self.generate_observation(self.poll_state.last.value, v, ctx).await;
+                    self.generate_observation(self.poll_state.last.value, v, truth_seen, ctx).await;
                 }
 
                 let current_poll = self.poll_state.current.take().unwrap();
@@ -73,18 +80,17 @@ impl UnsafePollingInterface {
                 self.poll_state.last = FinishedPoll {
                     sent: current_poll.at,
                     value: v,
+                    truth_seen,
                 };
 
                 // If a write is waiting- send it.
                 if let Some(waiting) = self.waiting_write {
+                    let write = WriteState::allocated(self.config.clock.now(ctx.time()), waiting, self.allocation.as_ref());
                     // Send it, guarded with new observed value.
-                    self.query_output.send(PlatformQuery::Interface(InterfaceQuery::PollingUnsafe(UnsafePollQuery::Write(waiting)))).await;
+                    self.query_output.send(PlatformQuery::Interface(InterfaceQuery::PollingUnsafe(UnsafePollQuery::Write(write.value)))).await;
 
                     // Log write.
-                    self.write_state = Some(WriteState {
-                        sent: ctx.time(),
-                        value: waiting,
-                    });
+                    self.write_state = Some(write);
 
                     // Clear waiting.
                     self.waiting_write = None;
@@ -97,14 +103,16 @@ impl UnsafePollingInterface {
                 }
             },
             // If write is complete...
-            UnsafePollReply::WriteComplete => {
+            UnsafePollReply::WriteComplete(truth_seen) => {
                 let successful_write = self.write_state.take().unwrap();
 
                 // Then written value is now last-
                 self.poll_state.last = FinishedPoll {
                     value: successful_write.value,
                     sent: successful_write.sent,
+                    truth_seen, // Unseen true events were overwritten.
                 };
+                self.poll_state.offset = successful_write.offset;
 
                 // Only send waiting writes on receipt of poll, sacrifices convergence time for improved safety.
                 // Therefore, schedule next poll
@@ -116,17 +124,19 @@ impl UnsafePollingInterface {
         }
     }
 
-    pub async fn generate_observation(&mut self, from: Value, to: Value, ctx: &mut Context<Self>) {
+    pub async fn generate_observation(&mut self, from: Value, to: Value, truth_seen: u64, ctx: &mut Context<Self>) {
         self.observation_output.send(Observation {
-            interval: Interval(self.poll_state.last.sent, ctx.time()),
+            interval: Interval(self.poll_state.last.sent, self.config.clock.now(ctx.time())), // Stamped by the interface's own clock.
             definition_predicate: match self.config.interp {
-                PollingInterpretation::Transition => Transition(from, to),
+                // Channel values are translated back to stock- deltas are the same in both.
+                PollingInterpretation::Transition => Transition(from + self.poll_state.offset, to + self.poll_state.offset),
                 PollingInterpretation::AllMut => AllMut(to - from),
-                PollingInterpretation::LastAssn => LastAssn(to)
+                PollingInterpretation::LastAssn => LastAssn(to + self.poll_state.offset)
             },
             source: self.name.clone(),
             platform_metadata: PlatformMetadata::Simulation(SimulationMetaData {
-                monotonic: self.poll_state.ordering
+                monotonic: self.poll_state.ordering,
+                truth: self.poll_state.last.truth_seen..truth_seen, // True events since the last poll.
             }),
         }).await;
         self.poll_state.ordering += 1;
@@ -135,7 +145,7 @@ impl UnsafePollingInterface {
     pub async fn poll(&mut self, _: (), ctx: &mut Context<Self>) {
         // Log poll.
         self.poll_state.current = Some(SentPoll {
-            at: ctx.time(),
+            at: self.config.clock.now(ctx.time()),
         });
         // Send query.
         self.query_output.send(PlatformQuery::Interface(InterfaceQuery::PollingUnsafe(UnsafePollQuery::Query))).await;
@@ -2,8 +2,8 @@ use crate::value::Value;
 
 #[derive(Debug, Copy, Clone)]
 pub enum UnsafePollReply {
-    Query(Value), // Query response- value at time of processing.
-    WriteComplete, // Write succeeded- no change since supplied value.
+    Query(Value, u64), // Query response- value at time of processing, and number of true events so far.
+    WriteComplete(u64), // Write succeeded, overwriting true events up to this number.
 }
 
 #[derive(Debug, Copy, Clone)]
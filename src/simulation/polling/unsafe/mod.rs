@@ -6,15 +6,20 @@ pub mod messages;
 use nexosim::model::{BuildContext, Model, ProtoModel};
 use nexosim::ports::Output;
 use nexosim::simulation::Mailbox;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use tai_time::MonotonicTime;
+use crate::allocation::{allocated, ChannelAllocation};
 use crate::interpreter::error::ConflictError;
 use crate::observations::Observation;
-use crate::simulation::driver::TruthRecord;
+use crate::simulation::driver::{DisplayRecord, LostUpdateRecord, RefusedSaleRecord, TruthRecord};
 use crate::simulation::network::network_delay::{NetworkConnection, NetworkParameters};
 use crate::simulation::polling::config::PollingInterfaceParameters;
 use crate::simulation::polling::r#unsafe::interface::UnsafePollingInterface;
 use crate::simulation::polling::r#unsafe::platform::UnsafePollingPlatform;
+use crate::simulation::script::PlatformScript;
+use crate::simulation::user::scripted::ScriptedUser;
 use crate::simulation::user::user::User;
 use crate::simulation::user::UserParameters;
 use crate::value::Value;
@@ -28,7 +33,7 @@ pub struct UnsafePollingConfig {
     pub(crate) initial_value: Value,
     pub(crate) network_params: NetworkParameters,
     pub(crate) interface_params: PollingInterfaceParameters,
-    pub(crate) user_params: UserParameters,
+    pub(crate) user_params: Option<UserParameters>, // None for scripted-only platforms.
 }
 
 impl UnsafePollingModel {
@@ -47,15 +52,27 @@ impl Model for UnsafePollingModel {}
 pub struct ProtoUnsafePollingModel {
     name: String,
     config: UnsafePollingConfig,
+    script: PlatformScript,
+    allocation: Option<ChannelAllocation>, // Platforms with a policy also refuse sales past zero.
+    seed: u64, // Seeds the network and user of this platform.
     pub observation_output: Output<Observation<MonotonicTime>>,
     pub truth_output: Output<TruthRecord>,
+    pub display_output: Output<DisplayRecord>,
+    pub refused_sale_output: Output<RefusedSaleRecord>,
+    pub lost_update_output: Output<LostUpdateRecord>,
 }
 impl ProtoUnsafePollingModel {
-    pub fn new(name: String, config: UnsafePollingConfig) -> ProtoUnsafePollingModel {
+    pub fn new(name: String, config: UnsafePollingConfig, script: PlatformScript, allocation: Option<ChannelAllocation>, seed: u64) -> ProtoUnsafePollingModel {
         ProtoUnsafePollingModel {
             name,
+            script,
+            allocation,
+            seed,
             observation_output: Default::default(),
             truth_output: Default::default(),
+            display_output: Default::default(),
+            refused_sale_output: Default::default(),
+            lost_update_output: Default::default(),
             config,
         }
     }
@@ -65,25 +82,33 @@ impl ProtoModel for ProtoUnsafePollingModel {
     type Model = UnsafePollingModel;
 
     fn build(self, cx: &mut BuildContext<Self>) -> Self::Model {
+        let mut rng = StdRng::seed_from_u64(self.seed);
         let mut model = UnsafePollingModel::new();
         // Initialise Platform Model
-        let mut platform = UnsafePollingPlatform::new(self.name.clone(), self.config.initial_value);
+        // Platform starts showing the initial value as allocated.
+        let shown = allocated(self.allocation.as_ref(), self.config.initial_value);
+        let mut platform = UnsafePollingPlatform::new(self.name.clone(), shown, self.allocation.is_some());
         let platform_in = Mailbox::new();
 
         // Initialise Network Connection Model
-        let mut network_connection = NetworkConnection::new(self.config.network_params);
+        let mut network_connection = NetworkConnection::new(self.config.network_params, self.script.delays, StdRng::seed_from_u64(rng.random()));
         let network_in = Mailbox::new();
 
         // Initialise Polling Interface Model
-        let mut interface = UnsafePollingInterface::new(self.name.clone(), self.config.interface_params, self.config.initial_value);
+        let mut interface = UnsafePollingInterface::new(self.name.clone(), self.config.interface_params, self.config.initial_value, self.allocation);
         let interface_in = Mailbox::new();
 
         // Initialise User
-        let mut user = User::new(self.config.user_params);
-        let user_in = Mailbox::new();
+        let user = self.config.user_params.map(|user_params| {
+            let mut user = User::new(user_params, StdRng::seed_from_u64(rng.random()));
+            // Connect user's output to platform's input.
+            user.action_output.connect(UnsafePollingPlatform::input, &platform_in);
+            user
+        });
 
-        // Connect user's output to platform's input.
-        user.action_output.connect(UnsafePollingPlatform::input, &platform_in);
+        // Initialise Scripted User, and connect its output to platform's input.
+        let mut scripted_user = ScriptedUser::new(self.script.actions);
+        scripted_user.action_output.connect(UnsafePollingPlatform::input, &platform_in);
 
         // Connect platform reply out to interface input.
         platform.reply_output.connect(NetworkConnection::input_1, &network_in);
@@ -99,6 +124,11 @@ impl ProtoModel for ProtoUnsafePollingModel {
         // Move External Truth Output into Submodel.
         platform.truth_output = self.truth_output;
 
+        // Move External Business Metric Outputs into Submodel.
+        platform.display_output = self.display_output;
+        platform.refused_sale_output = self.refused_sale_output;
+        platform.lost_update_output = self.lost_update_output;
+
         // Move External Observation Output into Submodel.
         interface.observation_output = self.observation_output;
         // interface = self.observation_output;
@@ -107,7 +137,10 @@ impl ProtoModel for ProtoUnsafePollingModel {
         cx.add_submodel(platform, platform_in, format!("UNSAFEPOLL-{}", self.name.clone()).as_str());
         cx.add_submodel(network_connection, network_in, format!("NETWORK-{}", self.name.clone()).as_str());
         cx.add_submodel(interface, interface_in, format!("UNSAFEINTERFACE-{}", self.name.clone()).as_str());
-        cx.add_submodel(user, user_in, format!("USER-{}", self.name).as_str());
+        if let Some(user) = user {
+            cx.add_submodel(user, Mailbox::new(), format!("USER-{}", self.name.clone()).as_str());
+        }
+        cx.add_submodel(scripted_user, Mailbox::new(), format!("SCRIPTEDUSER-{}", self.name).as_str());
         model
     }
 }
\ No newline at end of file
@@ -2,54 +2,84 @@ use nexosim::model::{Context, Model};
 use nexosim::ports::Output;
 use crate::simulation::messages::{InterfaceQuery, PlatformQuery, UserAction};
 use crate::predicates::DefinitionPredicate;
-use crate::simulation::driver::TruthRecord;
+use crate::simulation::driver::{DisplayRecord, LostUpdateRecord, RefusedSaleRecord, TruthRecord};
 use crate::simulation::polling::r#unsafe::messages::{UnsafePollQuery, UnsafePollReply};
 use crate::value::Value;
 
 pub struct UnsafePollingPlatform {
     name: String,
     current_value: Value,
+    refuse_out_of_stock: bool, // Refuse sales past zero, rather than selling into negative stock.
+    unseen_actions: Vec<UserAction>, // User actions since the interface last polled- erased by a blind write.
+    truth_sequence: u64, // Number of true events so far- tags observations in soundness checks.
     pub(crate) reply_output: Output<UnsafePollReply>,
-    pub(crate) truth_output: Output<TruthRecord>
+    pub(crate) truth_output: Output<TruthRecord>,
+    pub(crate) display_output: Output<DisplayRecord>,
+    pub(crate) lost_update_output: Output<LostUpdateRecord>,
+    pub(crate) refused_sale_output: Output<RefusedSaleRecord>,
 }
 
 impl UnsafePollingPlatform {
-    pub fn new(name: String, initial_value: Value) -> UnsafePollingPlatform {
+    pub fn new(name: String, initial_value: Value, refuse_out_of_stock: bool) -> UnsafePollingPlatform {
         UnsafePollingPlatform {
             name,
             current_value: initial_value,
+            refuse_out_of_stock,
+            unseen_actions: vec![],
+            truth_sequence: 0,
             reply_output: Default::default(),
-            truth_output: Default::default()
+            truth_output: Default::default(),
+            display_output: Default::default(),
+            lost_update_output: Default::default(),
+            refused_sale_output: Default::default(),
         }
     }
 
     // Input handler for safe platform.
     pub async fn input(&mut self, query: PlatformQuery, ctx: &mut Context<Self>) {
         match query {
-            PlatformQuery::User(user_action) => match user_action {
-                // When user triggered a mutation...
-                UserAction::Mutation(delta) => {
-                    self.current_value += delta;
-                    // Log to truth out.
-                    self.truth_output.send((DefinitionPredicate::AllMut(delta), ctx.time())).await;
-                }
-                // When user triggered an assignment...
-                UserAction::Assignment(value) => {
-                    self.current_value = value;
-                    // Log to truth out.
-                    self.truth_output.send((DefinitionPredicate::LastAssn(value), ctx.time())).await;
+            PlatformQuery::User(user_action) => {
+                match user_action {
+                    // When the channel shows too few units, the sale cannot happen.
+                    UserAction::Mutation(delta) if self.refuse_out_of_stock && self.current_value + delta < 0 => {
+                        self.refused_sale_output.send((self.name.clone(), -delta, ctx.time())).await;
+                        return;
+                    }
+                    // When user triggered a mutation...
+                    UserAction::Mutation(delta) => {
+                        self.current_value += delta;
+                        // Log to truth out.
+                        self.truth_output.send((self.name.clone(), DefinitionPredicate::AllMut(delta), ctx.time())).await;
+                        self.truth_sequence += 1;
+                    }
+                    // When user triggered an assignment...
+                    UserAction::Assignment(value) => {
+                        self.current_value = value;
+                        // Log to truth out.
+                        self.truth_output.send((self.name.clone(), DefinitionPredicate::LastAssn(value), ctx.time())).await;
+                        self.truth_sequence += 1;
+                    }
                 }
+                self.unseen_actions.push(user_action);
+                self.display_output.send((self.name.clone(), self.current_value, ctx.time())).await;
             },
             // If interface query...
             PlatformQuery::Interface(InterfaceQuery::PollingUnsafe(unsafe_query)) => match unsafe_query {
                 UnsafePollQuery::Query => {
                     // When getting a query- reply with current state.
-                    self.reply_output.send(UnsafePollReply::Query(self.current_value.clone())).await;
+                    self.reply_output.send(UnsafePollReply::Query(self.current_value.clone(), self.truth_sequence)).await;
+                    // Interface has now seen all prior actions.
+                    self.unseen_actions.clear();
                 },
                 UnsafePollQuery::Write(to_write) => {
+                    // Any actions the interface has not seen are overwritten- log them as lost.
+                    for erased in self.unseen_actions.drain(..) {
+                        self.lost_update_output.send((self.name.clone(), erased, ctx.time())).await;
+                    }
                     self.current_value = to_write;
+                    self.display_output.send((self.name.clone(), self.current_value, ctx.time())).await;
                     // Do write and send success.
-                    self.reply_output.send(UnsafePollReply::WriteComplete).await;
+                    self.reply_output.send(UnsafePollReply::WriteComplete(self.truth_sequence)).await;
                 }
             },
             x => panic!("Unexpected query type! {x:#?}")
@@ -58,4 +88,4 @@ impl UnsafePollingPlatform {
     }
 }
 
-impl Model for UnsafePollingPlatform {}
\ No newline at end of file
+impl Model for UnsafePollingPlatform {}
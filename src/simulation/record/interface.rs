@@ -7,13 +7,27 @@ use tai_time::MonotonicTime;
 use crate::intervals::Interval;
 use crate::observations::Observation;
 use crate::ordering::PlatformMetadata;
+use crate::simulation::clock::ClockParameters;
 use crate::simulation::data::SimulationMetaData;
 use crate::simulation::messages::{InterfaceQuery, PlatformQuery};
 use crate::simulation::record::messages::{RecordQuery, RecordReply};
+use crate::validation::{issue, Validate, ValidationIssue};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RecordInterfaceParameters {
     pub(crate) backoff: Duration,
+    #[serde(default)]
+    pub(crate) clock: ClockParameters, // Skew of the interface host's clock, which times deviation queries.
+}
+
+impl Validate for RecordInterfaceParameters {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        // Queries are scheduled after backoff- which must be in the future.
+        if self.backoff.is_zero() {
+            issue(issues, format!("{path}.backoff"), "Query backoff must be greater than zero");
+        }
+        self.clock.validate(&format!("{path}.clock"), issues);
+    }
 }
 
 pub struct DeviationState {
@@ -58,6 +72,7 @@ impl RecordInterface {
                         source: self.name.clone(),
                         platform_metadata: PlatformMetadata::Simulation(SimulationMetaData {
                             monotonic: logical_version,
+                            truth: logical_version..logical_version + 1, // One record per true event.
                         }),
                     }).await;
                 }
@@ -68,7 +83,7 @@ impl RecordInterface {
                 let last_sent = self.deviation_state.last_sent.take().unwrap();
 
                 // Find and update min/max deviation.
-                (self.deviation_state.min_deviation, self.deviation_state.max_deviation) = Self::find_deviation(last_sent, ctx.time(), deviated_clock);
+                (self.deviation_state.min_deviation, self.deviation_state.max_deviation) = Self::find_deviation(last_sent, self.config.clock.now(ctx.time()), deviated_clock);
 
                 // Schedule next deviation query!
                 ctx.schedule_event(ctx.time() + self.config.backoff, Self::query_deviation, ()).unwrap();
@@ -93,7 +108,7 @@ impl RecordInterface {
     }
 
     pub async fn query_deviation(&mut self, _: (), ctx: &mut Context<Self>) {
-        self.deviation_state.last_sent = Some(ctx.time());
+        self.deviation_state.last_sent = Some(self.config.clock.now(ctx.time())); // Interface's own clock.
         self.query_output.send(PlatformQuery::Interface(InterfaceQuery::Record(RecordQuery::Deviation))).await;
     }
 
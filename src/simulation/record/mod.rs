@@ -8,6 +8,8 @@ pub mod interface;
 use nexosim::model::{BuildContext, Model, ProtoModel};
 use nexosim::ports::Output;
 use nexosim::simulation::Mailbox;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use tai_time::MonotonicTime;
 use crate::observations::Observation;
@@ -15,6 +17,8 @@ use crate::simulation::driver::TruthRecord;
 use crate::simulation::network::network_delay::{NetworkConnection, NetworkParameters};
 use crate::simulation::record::interface::{RecordInterface, RecordInterfaceParameters};
 use crate::simulation::record::platform::{RecordPlatform, RecordPlatformParameters};
+use crate::simulation::script::PlatformScript;
+use crate::simulation::user::scripted::ScriptedUser;
 use crate::simulation::user::user::User;
 use crate::simulation::user::UserParameters;
 
@@ -25,7 +29,7 @@ pub struct RecordConfig {
     pub(crate) network_params: NetworkParameters,
     pub(crate) interface_params: RecordInterfaceParameters,
     pub(crate) platform_params: RecordPlatformParameters,
-    pub(crate) user_params: UserParameters,
+    pub(crate) user_params: Option<UserParameters>, // None for scripted-only platforms.
 }
 
 impl Model for RecordModel {}
@@ -34,14 +38,18 @@ impl Model for RecordModel {}
 pub struct ProtoRecordModel {
     name: String,
     config: RecordConfig,
+    script: PlatformScript,
+    seed: u64, // Seeds the network and user of this platform.
     pub observation_output: Output<Observation<MonotonicTime>>,
     pub truth_output: Output<TruthRecord>,
 }
 
 impl ProtoRecordModel {
-    pub fn new(name: String, config: RecordConfig) -> ProtoRecordModel {
+    pub fn new(name: String, config: RecordConfig, script: PlatformScript, seed: u64) -> ProtoRecordModel {
         ProtoRecordModel {
             name,
+            script,
+            seed,
             config,
             observation_output: Default::default(),
             truth_output: Default::default(),
@@ -53,6 +61,7 @@ impl ProtoModel for ProtoRecordModel {
     type Model = RecordModel;
 
     fn build(self, cx: &mut BuildContext<Self>) -> Self::Model {
+        let mut rng = StdRng::seed_from_u64(self.seed);
         let model = RecordModel {};
 
         // Initialise Platform Model
@@ -62,14 +71,19 @@ impl ProtoModel for ProtoRecordModel {
         let mut interface = RecordInterface::new(self.name.clone(), self.config.interface_params);
         let interface_in = Mailbox::new();
 
-        let mut network_connection = NetworkConnection::new(self.config.network_params);
+        let mut network_connection = NetworkConnection::new(self.config.network_params, self.script.delays, StdRng::seed_from_u64(rng.random()));
         let network_in = Mailbox::new();
 
-        let mut user = User::new(self.config.user_params);
-        let user_in =Mailbox::new();
+        let user = self.config.user_params.map(|user_params| {
+            let mut user = User::new(user_params, StdRng::seed_from_u64(rng.random()));
+            // Connect user's output to platform's input.
+            user.action_output.connect(RecordPlatform::input, &platform_in);
+            user
+        });
 
-        // Connect user's output to platform's input.
-        user.action_output.connect(RecordPlatform::input, &platform_in);
+        // Initialise Scripted User, and connect its output to platform's input.
+        let mut scripted_user = ScriptedUser::new(self.script.actions);
+        scripted_user.action_output.connect(RecordPlatform::input, &platform_in);
 
         // Connect platform reply out to interface input.
         platform.reply_output.connect(NetworkConnection::input_1, &network_in);
@@ -89,7 +103,10 @@ impl ProtoModel for ProtoRecordModel {
         cx.add_submodel(platform, platform_in, format!("RECORDPLATFORM-{}", self.name.clone()).as_str());
         cx.add_submodel(network_connection, network_in, format!("NETWORK-{}", self.name.clone()).as_str());
         cx.add_submodel(interface, interface_in, format!("RECORDINTERFACE-{}", self.name.clone()).as_str());
-        cx.add_submodel(user, user_in, format!("USER-{}", self.name).as_str());
+        if let Some(user) = user {
+            cx.add_submodel(user, Mailbox::new(), format!("USER-{}", self.name.clone()).as_str());
+        }
+        cx.add_submodel(scripted_user, Mailbox::new(), format!("SCRIPTEDUSER-{}", self.name).as_str());
         model
     }
 }
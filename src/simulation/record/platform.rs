@@ -44,14 +44,14 @@ impl RecordPlatform {
                     self.events_since.push(
                         (DefinitionPredicate::AllMut(delta), self.deviate_time(ctx.time()), self.logical_version)
                     );
-                    self.truth_output.send((DefinitionPredicate::AllMut(delta), ctx.time())).await;
+                    self.truth_output.send((self.name.clone(), DefinitionPredicate::AllMut(delta), ctx.time())).await;
                     self.logical_version += 1;
                 },
                 UserAction::Assignment(new) => {
                     self.events_since.push(
                         (DefinitionPredicate::LastAssn(new), self.deviate_time(ctx.time()), self.logical_version)
                     );
-                    self.truth_output.send((DefinitionPredicate::LastAssn(new), ctx.time())).await;
+                    self.truth_output.send((self.name.clone(), DefinitionPredicate::LastAssn(new), ctx.time())).await;
                     self.logical_version += 1;
                 }
             },
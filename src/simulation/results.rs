@@ -1,11 +1,16 @@
+use std::collections::BTreeMap;
 use std::time::Duration;
+use serde::{Deserialize, Serialize};
 use tai_time::MonotonicTime;
 use crate::interpreter::error::ConflictError;
 use crate::simulation::error::DivergenceError;
+use crate::simulation::statistics::ConfidenceInterval;
 
 #[derive(Debug)]
 pub struct SimulationResults {
     pub(crate) iterations: u64, // Number of iterations for this simulation.
+    pub(crate) convergence_times: Vec<Duration>, // Mean convergence time of each iteration which diverged and converged.
+    pub(crate) convergence_events: Vec<Duration>, // Every convergence, over all iterations.
     pub(crate) conflicts: Vec<ConflictError<MonotonicTime>>, // We log conflict causes for later inspection.
     pub(crate) divergence: Vec<DivergenceError>, // We log whole history for divergence.
     pub(crate) statistics: SimulationStatistics, // Overall Statistics for Simulation
@@ -18,4 +23,88 @@ pub struct SimulationStatistics {
     pub(crate) divergence_number: u64, // Number of divergences (INCORRECTNESS)
     pub(crate) average_time_to_conflict: Option<Duration>, // Average time taken for a conflict to arise when it does.
     pub(crate) average_time_to_divergence: Option<Duration>, // Average time taken for divergence to happen, when it does.
+    pub(crate) oversold_units: u64, // Units sold while the true value was at or below zero, over all iterations. (BUSINESS)
+    pub(crate) lost_updates: u64, // User actions erased by unsafe writes, over all iterations. (BUSINESS)
+    pub(crate) lost_sales: u64, // Units refused by channels showing too few, though in stock, over all iterations. (BUSINESS)
+    pub(crate) wrong_display_time: BTreeMap<String, Duration>, // Total time each platform displayed a wrong value, over all iterations. (BUSINESS)
+    pub(crate) unexpected_outcomes: u64, // Iterations ending on a value other than the script expected. (CORRECTNESS)
+    pub(crate) excluded_intervals: u64, // Observation intervals which excluded every true event of their platform, e.g. by clock skew. (CORRECTNESS)
+    pub(crate) soundness_violations: u64, // Observations inconsistent with the true events they reflect, in soundness mode. (CORRECTNESS)
+    pub(crate) conflict_rate_interval: ConfidenceInterval, // 95% Wilson interval for the conflict rate.
+    pub(crate) divergence_rate_interval: ConfidenceInterval, // 95% Wilson interval for the divergence rate.
+    pub(crate) mean_convergence_interval: Option<ConfidenceInterval>, // 95% interval for mean convergence time (seconds), given 2+ samples.
+}
+
+// Business metrics for a single iteration- computed by the driver from the truth stream and platform state.
+#[derive(Debug, Default)]
+pub struct IterationMetrics {
+    pub(crate) oversold_units: u64, // Units sold while the true value was at or below zero.
+    pub(crate) lost_updates: u64, // User actions erased by unsafe writes.
+    pub(crate) lost_sales: u64, // Units refused by channels showing too few, though in stock.
+    pub(crate) wrong_display_time: BTreeMap<String, Duration>, // Time each platform displayed a value other than the truth.
+    pub(crate) excluded_intervals: u64, // Observation intervals which excluded every true event of their platform.
+    pub(crate) soundness_violations: u64, // Observations inconsistent with the true events they reflect.
+}
+
+// Machine-readable results of one scenario- written by `synchro simulate`, read by `synchro compare`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioSummary {
+    pub(crate) scenario: String,
+    pub(crate) iterations: u64,
+    pub(crate) successes: u64,
+    pub(crate) conflicts: u64,
+    pub(crate) divergences: u64,
+    pub(crate) oversold_units: u64,
+    pub(crate) lost_updates: u64,
+    #[serde(default)]
+    pub(crate) lost_sales: u64,
+    pub(crate) convergence_times: Vec<f64>, // Seconds, one per iteration which diverged and converged.
+}
+
+impl ScenarioSummary {
+    pub fn new(scenario: String, results: &SimulationResults) -> ScenarioSummary {
+        ScenarioSummary {
+            scenario,
+            iterations: results.iterations,
+            successes: results.statistics.success_rate,
+            conflicts: results.statistics.conflict_number,
+            divergences: results.statistics.divergence_number,
+            oversold_units: results.statistics.oversold_units,
+            lost_updates: results.statistics.lost_updates,
+            lost_sales: results.statistics.lost_sales,
+            convergence_times: results.convergence_times.iter().map(|d| d.as_secs_f64()).collect(),
+        }
+    }
+}
+
+// Side-by-side comparison of several scenarios' statistics, one row each.
+pub fn summary_table(results: &[(String, SimulationResults)]) -> String {
+    let optional = |d: Option<Duration>| d.map(|d| format!("{d:.3?}")).unwrap_or("-".to_string());
+
+    let interval = |i: ConfidenceInterval| format!("[{:.3}, {:.3}]", i.lower, i.upper);
+
+    let mut table = format!(
+        "{:<40} {:>10} {:>8} {:>10} {:>12} {:>14} {:>16} {:>9} {:>13} {:>11} {:>16} {:>16}\n",
+        "Scenario", "Iterations", "Success", "Conflicts", "Divergences", "Avg Conflict", "Avg Divergence", "Oversold", "Lost Updates", "Lost Sales",
+        "Conflict Rate", "Divergence Rate"
+    );
+    for (name, result) in results {
+        let statistics = &result.statistics;
+        table += &format!(
+            "{:<40} {:>10} {:>8} {:>10} {:>12} {:>14} {:>16} {:>9} {:>13} {:>11} {:>16} {:>16}\n",
+            name,
+            result.iterations,
+            statistics.success_rate,
+            statistics.conflict_number,
+            statistics.divergence_number,
+            optional(statistics.average_time_to_conflict),
+            optional(statistics.average_time_to_divergence),
+            statistics.oversold_units,
+            statistics.lost_updates,
+            statistics.lost_sales,
+            interval(statistics.conflict_rate_interval),
+            interval(statistics.divergence_rate_interval),
+        );
+    }
+    table
 }
@@ -0,0 +1,83 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::simulation::messages::UserAction;
+use crate::simulation::network::network_delay::NetworkDirection;
+use crate::validation::{issue, Validate, ValidationIssue};
+use crate::value::Value;
+
+// Deterministic timeline for a scenario- exact user actions, and pinned network delays.
+// Used for regression scenarios of known interleavings, alongside (or instead of) the random users.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ScriptConfig {
+    #[serde(default)]
+    pub(crate) actions: Vec<ScriptedAction>,
+    #[serde(default)]
+    pub(crate) delays: Vec<PinnedDelay>,
+    #[serde(default)]
+    pub(crate) expected_value: Option<Value>, // Interpreter value expected at the end of every iteration.
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScriptedAction {
+    pub(crate) at: Duration, // Since simulation start.
+    pub(crate) platform: String,
+    pub(crate) action: UserAction,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PinnedDelay {
+    pub(crate) platform: String,
+    pub(crate) direction: NetworkDirection,
+    pub(crate) message: u64, // Index of the message in this direction, from 0.
+    pub(crate) delay: Duration,
+}
+
+// The part of a script which concerns a single platform.
+#[derive(Clone, Debug, Default)]
+pub struct PlatformScript {
+    pub(crate) actions: Vec<(Duration, UserAction)>,
+    pub(crate) delays: HashMap<(NetworkDirection, u64), Duration>,
+}
+
+impl ScriptConfig {
+    pub fn for_platform(&self, name: &str) -> PlatformScript {
+        PlatformScript {
+            actions: self.actions.iter()
+                .filter(|action| action.platform == name)
+                .map(|action| (action.at, action.action))
+                .collect(),
+            delays: self.delays.iter()
+                .filter(|delay| delay.platform == name)
+                .map(|delay| ((delay.direction, delay.message), delay.delay))
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty() && self.delays.is_empty() && self.expected_value.is_none()
+    }
+}
+
+impl Validate for ScriptConfig {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        // Events can only be scheduled strictly in the future.
+        for (i, action) in self.actions.iter().enumerate() {
+            if action.at.is_zero() {
+                issue(issues, format!("{path}.actions[{i}].at"), "Scripted action must happen after the simulation starts");
+            }
+        }
+
+        let mut pinned = HashSet::new();
+        for (i, delay) in self.delays.iter().enumerate() {
+            if delay.delay.is_zero() {
+                issue(issues, format!("{path}.delays[{i}].delay"), "Pinned delay must be greater than zero");
+            }
+            if !pinned.insert((&delay.platform, delay.direction, delay.message)) {
+                issue(issues, format!("{path}.delays[{i}]"), format!(
+                    "Message {} {:?} on {} is pinned more than once", delay.message, delay.direction, delay.platform
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use tai_time::MonotonicTime;
+use crate::intervals::Interval;
+use crate::ordering::PlatformMetadata;
+use crate::simulation::driver::ObservationRecord;
+
+// Checks every observation against the true events it reflects- tagged by the platform which caused them.
+// Catches interface bugs before they show up as divergences.
+#[derive(Default)]
+pub struct SoundnessChecker {
+    last: HashMap<String, (u64, Range<u64>)>, // Per source, metadata ordering and truth tags of the last observation.
+}
+
+impl SoundnessChecker {
+    pub fn check(&mut self, observation: &ObservationRecord, truth_times: &HashMap<String, Vec<MonotonicTime>>) -> Vec<String> {
+        let PlatformMetadata::Simulation(metadata) = &observation.platform_metadata else {
+            return vec![]; // Only simulated observations are tagged.
+        };
+        let Interval(start, end) = observation.interval;
+        let mut violations = vec![];
+
+        // The interval must contain every true event the observation reflects.
+        if metadata.truth.is_empty() {
+            violations.push(format!("Observation #{} ({:?}) reflects no true event", metadata.monotonic, observation.definition_predicate));
+        }
+        let times = truth_times.get(&observation.source).map(|t| t.as_slice()).unwrap_or_default();
+        for tag in metadata.truth.clone() {
+            match times.get(tag as usize) {
+                Some(at) if *at < start || *at > end => violations.push(format!(
+                    "Interval {:?} of observation #{} excludes true event #{tag} at {at:?}", observation.interval, metadata.monotonic
+                )),
+                Some(_) => (),
+                None => violations.push(format!(
+                    "Observation #{} reflects true event #{tag}, which has not happened", metadata.monotonic
+                )),
+            }
+        }
+
+        // Metadata ordering must match the order of the true events.
+        if let Some((last_monotonic, last_truth)) = self.last.get(&observation.source) {
+            if metadata.monotonic <= *last_monotonic {
+                violations.push(format!("Observation #{} is ordered at or before previous observation #{last_monotonic}", metadata.monotonic));
+            } else if metadata.truth.start < last_truth.end {
+                violations.push(format!(
+                    "Observation #{} reflects true events from #{}, but previous observation #{last_monotonic} reflected up to #{}",
+                    metadata.monotonic, metadata.truth.start, last_truth.end.saturating_sub(1)
+                ));
+            }
+        }
+        self.last.insert(observation.source.clone(), (metadata.monotonic, metadata.truth.clone()));
+
+        violations
+    }
+}
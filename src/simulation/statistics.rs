@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+// Confidence intervals over simulation results, and the rule for stopping once they are narrow enough.
+
+pub const Z_95: f64 = 1.959964; // Two-sided 95% normal quantile.
+pub const MIN_ITERATIONS: u64 = 30; // Fewer iterations make the normal approximations unreliable.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConfidenceInterval {
+    pub(crate) lower: f64,
+    pub(crate) upper: f64,
+}
+
+impl ConfidenceInterval {
+    pub fn width(&self) -> f64 {
+        self.upper - self.lower
+    }
+}
+
+// Wilson score interval for a proportion- well behaved for rates near 0, unlike the normal interval.
+pub fn wilson(successes: u64, trials: u64, z: f64) -> ConfidenceInterval {
+    if trials == 0 {
+        return ConfidenceInterval { lower: 0.0, upper: 1.0 };
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = z * z;
+
+    let centre = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let margin = (z / (1.0 + z2 / n)) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    ConfidenceInterval { lower: (centre - margin).max(0.0), upper: (centre + margin).min(1.0) }
+}
+
+// Normal interval for a mean, in seconds. Needs at least two samples.
+pub fn mean_interval(samples: &[Duration], z: f64) -> Option<ConfidenceInterval> {
+    if samples.len() < 2 {
+        return None;
+    }
+    let n = samples.len() as f64;
+    let secs: Vec<f64> = samples.iter().map(|d| d.as_secs_f64()).collect();
+    let mean = secs.iter().sum::<f64>() / n;
+    let variance = secs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    let margin = z * (variance / n).sqrt();
+    Some(ConfidenceInterval { lower: (mean - margin).max(0.0), upper: mean + margin })
+}
+
+// Run until every interval is narrower than its target, with the iteration count as a cap.
+#[derive(Clone, Copy, Debug)]
+pub struct StoppingRule {
+    pub(crate) rate_width: f64, // Target width of the conflict and divergence rate intervals.
+    pub(crate) convergence_width: Option<Duration>, // Target width of the mean convergence time interval, if any.
+}
+
+impl StoppingRule {
+    pub fn satisfied(&self, iterations: u64, conflicts: u64, divergences: u64, convergence_times: &[Duration]) -> bool {
+        if iterations < MIN_ITERATIONS {
+            return false;
+        }
+        if wilson(conflicts, iterations, Z_95).width() > self.rate_width
+            || wilson(divergences, iterations, Z_95).width() > self.rate_width {
+            return false;
+        }
+        // Scenarios which rarely diverge may never estimate convergence- the rates alone decide.
+        match (self.convergence_width, mean_interval(convergence_times, Z_95)) {
+            (Some(target), Some(interval)) => interval.width() <= target.as_secs_f64(),
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use clap::ValueEnum;
+use serde::Serialize;
+use tai_time::MonotonicTime;
+use crate::simulation::config::{PlatformConfig, SimulationConfig};
+use crate::value::Value;
+
+// Per-iteration trajectory export, for plotting divergence windows and convergence behaviour.
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum TimeSeriesFormat {
+    Csv, // Header row, then one row per point.
+    Jsonl, // One JSON object per line.
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Series {
+    Truth, // True value after each true event.
+    Interpreter, // Interpreter output (no value on conflict).
+    Display, // Value displayed by a platform.
+}
+
+#[derive(Debug, Serialize)]
+struct TimeSeriesPoint<'a> {
+    scenario: &'a str, // Scenario label- lets runs of different interpretations share a plot.
+    iteration: u64,
+    time: f64, // Seconds since simulation start.
+    series: Series,
+    source: &'a str, // Platform the point came from, empty for truth and interpreter.
+    interpretation: &'a str, // How the source platform is interpreted, empty for truth and interpreter.
+    value: Option<Value>,
+    detail: String, // True event, or conflict reason.
+}
+
+pub struct TimeSeriesWriter {
+    scenario: String,
+    format: TimeSeriesFormat,
+    interpretations: HashMap<String, String>, // Platform name -> interpretation label.
+    out: BufWriter<File>,
+}
+
+impl TimeSeriesWriter {
+    pub fn create(path: &Path, format: TimeSeriesFormat, scenario: String, cfg: &SimulationConfig) -> TimeSeriesWriter {
+        let interpretations = cfg.platforms.iter().map(|(name, platform)| (name.clone(), match platform {
+            PlatformConfig::PollingSafe(safe_cfg) => format!("{:?}", safe_cfg.interface_params.interp),
+            PlatformConfig::PollingUnsafe(unsafe_cfg) => format!("{:?}", unsafe_cfg.interface_params.interp),
+            PlatformConfig::Record(_) => "Record".to_string(),
+        })).collect();
+
+        let mut out = BufWriter::new(File::create(path).unwrap());
+        if let TimeSeriesFormat::Csv = format {
+            writeln!(out, "scenario,iteration,time,series,source,interpretation,value,detail").unwrap();
+        }
+
+        TimeSeriesWriter { scenario, format, interpretations, out }
+    }
+
+    pub fn record(&mut self, iteration: u64, at: MonotonicTime, series: Series, source: &str, value: Option<Value>, detail: String) {
+        let point = TimeSeriesPoint {
+            scenario: &self.scenario,
+            iteration,
+            time: at.duration_since(MonotonicTime::EPOCH).as_secs_f64(),
+            series,
+            source,
+            interpretation: self.interpretations.get(source).map(|s| s.as_str()).unwrap_or(""),
+            value,
+            detail,
+        };
+
+        match self.format {
+            TimeSeriesFormat::Csv => writeln!(self.out, "{},{},{},{},{},{},{},{}",
+                csv_field(point.scenario),
+                point.iteration,
+                point.time,
+                serde_json::to_value(point.series).unwrap().as_str().unwrap(),
+                csv_field(point.source),
+                csv_field(point.interpretation),
+                point.value.map(|v| v.to_string()).unwrap_or_default(),
+                csv_field(&point.detail),
+            ),
+            TimeSeriesFormat::Jsonl => writeln!(self.out, "{}", serde_json::to_string(&point).unwrap()),
+        }.unwrap();
+    }
+
+    pub fn flush(&mut self) {
+        self.out.flush().unwrap();
+    }
+}
+
+// Quote fields which could contain separators- predicates print as e.g. Transition(1, 2).
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -3,8 +3,10 @@ use serde::{Deserialize, Serialize};
 use tai_time::MonotonicTime;
 use crate::value::Value;
 use crate::simulation::config::serde_monotonic_helper;
+use crate::validation::{issue, Validate, ValidationIssue};
 
 pub mod user;
+pub mod scripted;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UserParameters {
@@ -14,4 +16,19 @@ pub struct UserParameters {
     pub(crate) start_after: Duration,
     #[serde(with="serde_monotonic_helper")]
     pub(crate) until: MonotonicTime
-}
\ No newline at end of file
+}
+
+impl Validate for UserParameters {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        // Exponential distributions require a positive, finite rate.
+        if !(self.average_sales_per_hour.is_finite() && self.average_sales_per_hour > 0.0) {
+            issue(issues, format!("{path}.average_sales_per_hour"), format!("Sale rate must be positive, got {}", self.average_sales_per_hour));
+        }
+        if !(self.average_edits_per_day.is_finite() && self.average_edits_per_day > 0.0) {
+            issue(issues, format!("{path}.average_edits_per_day"), format!("Edit rate must be positive, got {}", self.average_edits_per_day));
+        }
+        if MonotonicTime::EPOCH + self.start_after >= self.until {
+            issue(issues, format!("{path}.start_after"), "User starts after it stops- it will never act");
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::time::Duration;
+use nexosim::model::{Context, InitializedModel, Model};
+use nexosim::ports::Output;
+use crate::simulation::messages::{PlatformQuery, UserAction};
+
+// User which performs exactly the actions in a script, at exactly the given times.
+pub struct ScriptedUser {
+    actions: Vec<(Duration, UserAction)>, // Since simulation start.
+    pub(crate) action_output: Output<PlatformQuery>,
+}
+impl ScriptedUser {
+    pub fn new(actions: Vec<(Duration, UserAction)>) -> ScriptedUser {
+        ScriptedUser {
+            actions,
+            action_output: Default::default(),
+        }
+    }
+
+    pub async fn do_action(&mut self, action: UserAction) {
+        self.action_output.send(PlatformQuery::User(action)).await;
+    }
+}
+
+impl Model for ScriptedUser {
+    async fn init(self, ctx: &mut Context<Self>) -> InitializedModel<Self> {
+        // Schedule whole script up front. Actions at the same time keep their scripted order.
+        for (at, action) in &self.actions {
+            let at = ctx.time() + *at;
+            ctx.schedule_event(at, Self::do_action, *action).expect(format!("Error Scheduling At: {:?}", at).as_str());
+        }
+
+        self.into()
+    }
+}
@@ -2,6 +2,7 @@ use std::time::Duration;
 use nexosim::model::{Context, InitializedModel, Model};
 use nexosim::ports::Output;
 use rand::Rng;
+use rand::rngs::StdRng;
 use rand_distr::Exp;
 use crate::simulation::messages::{PlatformQuery, UserAction};
 use crate::simulation::user::UserParameters;
@@ -10,14 +11,16 @@ pub struct User {
     config: UserParameters,
     sale_distribution: Exp<f64>,
     edit_distribution: Exp<f64>,
+    rng: StdRng, // Seeded per iteration, so that runs can be reproduced.
     pub(crate) action_output: Output<PlatformQuery>,
 }
 impl User {
-    pub fn new(config: UserParameters) -> User {
+    pub fn new(config: UserParameters, rng: StdRng) -> User {
         User {
             sale_distribution: Exp::new(config.average_sales_per_hour / 60.0 / 60.0 / 1000.0).unwrap(), // Time Between Sales in milliseconds
             edit_distribution: Exp::new(config.average_edits_per_day / 24.0 / 60.0 / 60.0 / 1000.0).unwrap(), // Time Between Edits in milliseconds.
             config,
+            rng,
             action_output: Default::default(),
         }
     }
@@ -31,7 +34,7 @@ impl User {
             // Do Sale
             self.action_output.send(PlatformQuery::User(UserAction::Assignment(self.config.edit_to))).await;
             // Schedule next sale
-            let next_edit = ctx.time() + Duration::from_millis(rand::rng().sample(self.edit_distribution).round() as u64 + 1);
+            let next_edit = ctx.time() + Duration::from_millis(self.rng.sample(self.edit_distribution).round() as u64 + 1);
             if next_edit < self.config.until {
                 ctx.schedule_event(next_edit, Self::do_edit, ()).expect(format!("Error Scheduling At: {:?}", next_edit).as_str());
             }
@@ -47,7 +50,7 @@ impl User {
             // Do Sale
             self.action_output.send(PlatformQuery::User(UserAction::Mutation(-1))).await;
             // Schedule next sale
-            let next_sale = ctx.time() + Duration::from_millis(rand::rng().sample(self.sale_distribution).round() as u64 + 1);
+            let next_sale = ctx.time() + Duration::from_millis(self.rng.sample(self.sale_distribution).round() as u64 + 1);
             if next_sale < self.config.until {
                 ctx.schedule_event(next_sale, Self::do_sale, ()).expect(format!("Error Scheduling At: {:?}", next_sale).as_str());
             }
@@ -56,16 +59,16 @@ impl User {
 }
 
 impl Model for User {
-    async fn init(self, ctx: &mut Context<Self>) -> InitializedModel<Self> {
+    async fn init(mut self, ctx: &mut Context<Self>) -> InitializedModel<Self> {
         // Schedule first sale after start time. (For observation of deviation.)
         let first_sale = ctx.time()
             + self.config.start_after
-            + Duration::from_millis(rand::rng().sample(self.sale_distribution).round() as u64);
+            + Duration::from_millis(self.rng.sample(self.sale_distribution).round() as u64);
         ctx.schedule_event(first_sale, Self::do_sale, ()).unwrap();
 
         let first_edit = ctx.time()
             + self.config.start_after
-            + Duration::from_millis(rand::rng().sample(self.edit_distribution).round() as u64);
+            + Duration::from_millis(self.rng.sample(self.edit_distribution).round() as u64);
         ctx.schedule_event(first_edit, Self::do_edit, ()).unwrap();
 
 
@@ -14,6 +14,7 @@ use crate::simulation::user::UserParameters;
 
 pub fn make_demo_sim() -> SimulationConfig {
     SimulationConfig {
+        name: None,
         initial_value: 100,
         until: MonotonicTime::new(10000, 0).unwrap(),
         max_divergence_before_error: Duration::new(1, 0),
@@ -27,14 +28,15 @@ pub fn make_demo_sim() -> SimulationConfig {
                 interface_params: PollingInterfaceParameters {
                     interp: PollingInterpretation::Transition,
                     backoff: Duration::from_millis(200),
+                    clock: Default::default(),
                 },
-                user_params: UserParameters {
+                user_params: Some(UserParameters {
                     until: MonotonicTime::new(100000, 0).unwrap(),
                     average_sales_per_hour: 2.0,
                     average_edits_per_day: 1.0,
                     edit_to: 100,
                     start_after: Default::default(),
-                },
+                }),
             })),
             ("Polling2".to_string(), PlatformConfig::PollingSafe(SafePollingConfig {
                 initial_value: 100,
@@ -45,31 +47,40 @@ pub fn make_demo_sim() -> SimulationConfig {
                 interface_params: PollingInterfaceParameters {
                     interp: PollingInterpretation::Transition,
                     backoff: Duration::from_millis(200),
+                    clock: Default::default(),
                 },
-                user_params: UserParameters {
+                user_params: Some(UserParameters {
                     until: MonotonicTime::new(100000, 0).unwrap(),
                     average_sales_per_hour: 10.0,
                     average_edits_per_day: 5.0,
                     edit_to: 100,
                     start_after: Default::default(),
-                },
+                }),
             })),
             ("Record1".to_string(), PlatformConfig::Record(RecordConfig {
                 network_params: NetworkParameters { size: 40.0, scale: 2.0 },
                 interface_params: RecordInterfaceParameters {
                     backoff: Duration::from_millis(200),
+                    clock: Default::default(),
                 },
                 platform_params: RecordPlatformParameters {
                     deviation: TimeDelta::milliseconds(-400),
                 },
-                user_params: UserParameters {
+                user_params: Some(UserParameters {
                     until: MonotonicTime::new(100000, 0).unwrap(),
                     average_sales_per_hour: 100.0,
                     average_edits_per_day: 20.0,
                     edit_to: 100,
                     start_after: Duration::from_millis(1000),
-                },
+                }),
             }))
-        ])
+        ]),
+        interpreter: Default::default(),
+        script: Default::default(),
+        registry: Default::default(),
+        allocation: Default::default(),
+        soundness: false,
+        seed: None,
+        expect: None,
     }
 }
\ No newline at end of file
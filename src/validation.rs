@@ -0,0 +1,230 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+use tai_time::MonotonicTime;
+use crate::config::Config;
+use crate::real_world::config::{PlatformConfig as RealWorldPlatformConfig, RealWorldConfig};
+use crate::real_world::reconciliation::Reconciliation;
+use crate::simulation::config::{PlatformConfig as SimulationPlatformConfig, SimulationConfig};
+use crate::simulation::user::UserParameters;
+
+// A semantic problem with a config, located by its JSON path.
+#[derive(Debug, Clone)]
+pub struct ValidationIssue {
+    pub(crate) path: String, // JSON path, e.g. $.Simulation[0].platforms.Polling1
+    pub(crate) message: String,
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+// Semantic checks for a config section- implemented beside each config type.
+// Every problem is reported, rather than stopping at the first.
+pub trait Validate {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>);
+}
+
+pub fn issue(issues: &mut Vec<ValidationIssue>, path: String, message: impl Into<String>) {
+    issues.push(ValidationIssue { path, message: message.into() });
+}
+
+// Parse a config file, and run all semantic checks over it.
+pub fn validate_file(path: &Path) -> Result<Vec<ValidationIssue>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read: {e}"))?;
+    let config: Config = serde_json::from_str(&contents).map_err(|e| format!("Failed to parse: {e}"))?;
+
+    let mut issues = vec![];
+    config.validate("$", &mut issues);
+    Ok(issues)
+}
+
+impl Validate for Config {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        match self {
+            Config::RealWorld(cfg) => cfg.validate(&format!("{path}.RealWorld"), issues),
+            Config::Simulation(cfgs) => {
+                if cfgs.is_empty() {
+                    issue(issues, format!("{path}.Simulation"), "No simulations configured");
+                }
+                for (i, cfg) in cfgs.iter().enumerate() {
+                    cfg.validate(&format!("{path}.Simulation[{i}]"), issues);
+                }
+            }
+        }
+    }
+}
+
+impl Validate for SimulationConfig {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        if self.until <= MonotonicTime::EPOCH {
+            issue(issues, format!("{path}.until"), "Simulation must run for some time (until > 0)");
+        }
+        if self.platforms.is_empty() {
+            issue(issues, format!("{path}.platforms"), "No platforms configured");
+        }
+
+        // Sorted, so that problems are reported in a stable order.
+        let mut names: Vec<&String> = self.platforms.keys().collect();
+        names.sort();
+        for name in names {
+            let platform_path = format!("{path}.platforms.{name}");
+            let platform = &self.platforms[name];
+            platform.validate(&platform_path, issues);
+
+            // Cross-checks against the simulation itself.
+            let (variant, initial_value, user_params) = match platform {
+                SimulationPlatformConfig::PollingSafe(cfg) => ("PollingSafe", Some(cfg.initial_value), &cfg.user_params),
+                SimulationPlatformConfig::PollingUnsafe(cfg) => ("PollingUnsafe", Some(cfg.initial_value), &cfg.user_params),
+                SimulationPlatformConfig::Record(cfg) => ("Record", None, &cfg.user_params),
+            };
+            if let Some(initial_value) = initial_value {
+                if initial_value != self.initial_value {
+                    issue(issues, format!("{platform_path}.{variant}.initial_value"), format!(
+                        "Platform starts at {initial_value}, but the simulation starts at {}", self.initial_value
+                    ));
+                }
+            }
+            if let Some(user_params) = user_params {
+                validate_user_until(user_params, self.until, &format!("{platform_path}.{variant}.user_params"), issues);
+            }
+        }
+
+        self.registry.validate(&format!("{path}.registry"), issues);
+        for name in self.registry.names() {
+            if !self.platforms.contains_key(name) {
+                issue(issues, format!("{path}.registry.{name}"), format!("No platform named {name}"));
+            }
+        }
+
+        self.allocation.validate(&format!("{path}.allocation"), issues);
+        for name in self.allocation.names() {
+            if !self.platforms.contains_key(name) {
+                issue(issues, format!("{path}.allocation"), format!("No platform named {name}"));
+            }
+        }
+
+        self.interpreter.validate(&format!("{path}.interpreter"), issues);
+        for (i, crash) in self.interpreter.crashes.iter().enumerate() {
+            if MonotonicTime::EPOCH + crash.at > self.until {
+                issue(issues, format!("{path}.interpreter.crashes[{i}].at"), "Crash happens after the simulation ends");
+            }
+        }
+        if let Some(expect) = &self.expect {
+            expect.validate(&format!("{path}.expect"), issues);
+        }
+
+        // Scripts must only refer to configured platforms, within the simulation.
+        self.script.validate(&format!("{path}.script"), issues);
+        for (i, action) in self.script.actions.iter().enumerate() {
+            if !self.platforms.contains_key(&action.platform) {
+                issue(issues, format!("{path}.script.actions[{i}].platform"), format!("No platform named {}", action.platform));
+            }
+            if MonotonicTime::EPOCH + action.at > self.until {
+                issue(issues, format!("{path}.script.actions[{i}].at"), "Scripted action happens after the simulation ends");
+            }
+        }
+        for (i, delay) in self.script.delays.iter().enumerate() {
+            if !self.platforms.contains_key(&delay.platform) {
+                issue(issues, format!("{path}.script.delays[{i}].platform"), format!("No platform named {}", delay.platform));
+            }
+        }
+    }
+}
+
+fn validate_user_until(user_params: &UserParameters, until: MonotonicTime, path: &str, issues: &mut Vec<ValidationIssue>) {
+    if user_params.until > until {
+        issue(issues, format!("{path}.until"), format!(
+            "User acts until {}s, after the simulation ends at {}s",
+            user_params.until.duration_since(MonotonicTime::EPOCH).as_secs(),
+            until.duration_since(MonotonicTime::EPOCH).as_secs(),
+        ));
+    }
+}
+
+impl Validate for SimulationPlatformConfig {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        match self {
+            SimulationPlatformConfig::PollingSafe(cfg) => {
+                let path = format!("{path}.PollingSafe");
+                cfg.network_params.validate(&format!("{path}.network_params"), issues);
+                cfg.interface_params.validate(&format!("{path}.interface_params"), issues);
+                if let Some(user_params) = &cfg.user_params {
+                    user_params.validate(&format!("{path}.user_params"), issues);
+                }
+            }
+            SimulationPlatformConfig::PollingUnsafe(cfg) => {
+                let path = format!("{path}.PollingUnsafe");
+                cfg.network_params.validate(&format!("{path}.network_params"), issues);
+                cfg.interface_params.validate(&format!("{path}.interface_params"), issues);
+                if let Some(user_params) = &cfg.user_params {
+                    user_params.validate(&format!("{path}.user_params"), issues);
+                }
+            }
+            SimulationPlatformConfig::Record(cfg) => {
+                let path = format!("{path}.Record");
+                cfg.network_params.validate(&format!("{path}.network_params"), issues);
+                cfg.interface_params.validate(&format!("{path}.interface_params"), issues);
+                if let Some(user_params) = &cfg.user_params {
+                    user_params.validate(&format!("{path}.user_params"), issues);
+                }
+            }
+        }
+    }
+}
+
+impl Validate for RealWorldConfig {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        if self.platforms.is_empty() {
+            issue(issues, format!("{path}.platforms"), "No platforms configured");
+        }
+
+        let mut seen = HashSet::new();
+        for (i, (name, platform)) in self.platforms.iter().enumerate() {
+            if !seen.insert(name) {
+                issue(issues, format!("{path}.platforms[{i}][0]"), format!("Platform name {name} is used more than once"));
+            }
+            platform.validate(&format!("{path}.platforms[{i}][1]"), issues);
+        }
+
+        self.allocation.validate(&format!("{path}.allocation"), issues);
+        for name in self.allocation.names() {
+            if !seen.contains(name) {
+                issue(issues, format!("{path}.allocation"), format!("No platform named {name}"));
+            }
+        }
+
+        self.registry.validate(&format!("{path}.registry"), issues);
+        for name in self.registry.names() {
+            if !seen.contains(name) {
+                issue(issues, format!("{path}.registry.{name}"), format!("No platform named {name}"));
+            }
+        }
+
+        self.scheduling.validate(&format!("{path}.scheduling"), issues);
+
+        match self.reconciliation() {
+            Reconciliation::Source(name) if !seen.contains(&name) => {
+                issue(issues, format!("{path}.reconciliation.Source"), format!("No platform named {name}"));
+            }
+            Reconciliation::InitialValue if self.initial_value.is_none() => {
+                issue(issues, format!("{path}.initial_value"), "InitialValue reconciliation needs an initial value");
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Validate for RealWorldPlatformConfig {
+    fn validate(&self, path: &str, issues: &mut Vec<ValidationIssue>) {
+        match self {
+            RealWorldPlatformConfig::Polling(cfg) => cfg.validate(&format!("{path}.Polling"), issues),
+            RealWorldPlatformConfig::Records(cfg) => cfg.validate(&format!("{path}.Records"), issues),
+            RealWorldPlatformConfig::Shopify(cfg) => cfg.validate(&format!("{path}.Shopify"), issues),
+            RealWorldPlatformConfig::WooCommerce(cfg) => cfg.validate(&format!("{path}.WooCommerce"), issues),
+        }
+    }
+}